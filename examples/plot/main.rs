@@ -20,9 +20,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         pi_proportional_gain: 178.0,
         pi_integral_gain: 0.0001,
         omega_zero: omega_n,
+        ..Default::default()
     };
 
-    let mut pll = SogiPll::new(config);
+    let pll = SogiPll::new(config);
 
     let x = RANGE.step(SAMPLING_TIME);
 
@@ -34,7 +35,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         })
         .collect();
 
-    let pll_out: Vec<PllResult> = y_cos.clone().into_iter().map(|x| pll.update(x)).collect();
+    let pll_out: Vec<PllResult> = pll.process_iter(y_cos.clone().into_iter()).collect();
 
     let theta: Vec<f32> = pll_out.iter().map(|x| x.theta).collect();
     let rms: Vec<f32> = pll_out.iter().map(|x| x.v_rms()).collect();
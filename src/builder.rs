@@ -0,0 +1,121 @@
+//! Builder for the PLL configuration
+
+use crate::{is_positive, Float, PllConfig, PllError};
+
+/// Builder for [`PllConfig`]
+///
+/// Starts from [`PllConfig::default`], the recommended 50Hz parameters with a 12kHz sample
+/// rate.
+#[derive(Clone, Debug)]
+pub struct PllConfigBuilder<T = f32> {
+    config: PllConfig<T>,
+}
+
+impl<T: Float> PllConfigBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            config: PllConfig::default(),
+        }
+    }
+
+    /// Sample rate in Hz, sets `sample_time = 1 / rate`
+    pub fn sample_rate_hz(mut self, rate: T) -> Self {
+        self.config.sample_time = T::ONE / rate;
+        self
+    }
+
+    /// Nominal grid frequency in Hz, sets `omega_zero = 2π·f`
+    pub fn nominal_frequency_hz(mut self, frequency: T) -> Self {
+        self.config.omega_zero = T::PI2 * frequency;
+        self
+    }
+
+    pub fn sogi_k(mut self, k: T) -> Self {
+        self.config.sogi_k = k;
+        self
+    }
+
+    /// Proportional and integral gains of the PI loop
+    pub fn pi_gains(mut self, proportional: T, integral: T) -> Self {
+        self.config.pi_proportional_gain = proportional;
+        self.config.pi_integral_gain = integral;
+        self
+    }
+
+    /// Validate and return the configuration
+    ///
+    /// Rejects a sample rate that is not positive and finite, and a nominal frequency or
+    /// `sogi_k` that is not positive.
+    pub fn build(self) -> Result<PllConfig<T>, PllError> {
+        let sample_time = self.config.sample_time;
+        if !(sample_time > T::ZERO && sample_time < T::INFINITY) {
+            return Err(PllError::InvalidSampleRate);
+        }
+        if !is_positive(self.config.omega_zero) {
+            return Err(PllError::InvalidOmegaZero);
+        }
+        if !is_positive(self.config.sogi_k) {
+            return Err(PllError::InvalidGain);
+        }
+        Ok(self.config)
+    }
+}
+
+impl<T: Float> Default for PllConfigBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> PllConfig<T> {
+    /// Configuration with the PI gains derived from a loop bandwidth and damping
+    ///
+    /// Uses the second order SRF-PLL design equation for the proportional gain with a unit
+    /// amplitude input, `ωn = 2π · bandwidth_hz` and `Kp = 2ζ · ωn`. For example
+    /// `bandwidth_hz = 20` and `damping = 1/√2` give the recommended `Kp ≈ 178`.
+    /// Scale `Kp` by `1 / amplitude` for other input amplitudes.
+    ///
+    /// The integral path of this loop accumulates its own output rather than the phase
+    /// error, so the textbook `Ki = ωn²` would make it diverge. The recommended
+    /// `Ki = 0.0001` is kept instead. Other fields use the [`PllConfigBuilder`] defaults.
+    ///
+    /// Validated as in [`PllConfigBuilder::build`].
+    pub fn from_loop_spec(
+        sample_rate: T,
+        nominal_hz: T,
+        bandwidth_hz: T,
+        damping: T,
+    ) -> Result<Self, PllError> {
+        let omega_n = T::PI2 * bandwidth_hz;
+        let mut builder = PllConfigBuilder::new()
+            .sample_rate_hz(sample_rate)
+            .nominal_frequency_hz(nominal_hz);
+        builder.config.pi_proportional_gain = T::from_f32(2.0) * damping * omega_n;
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_spec_gives_the_recommended_gain() {
+        let config =
+            PllConfig::from_loop_spec(12000.0, 50.0, 20.0, core::f32::consts::FRAC_1_SQRT_2)
+                .unwrap();
+        assert!((config.pi_proportional_gain - 178.0).abs() < 0.5);
+        assert_eq!(
+            config.pi_integral_gain,
+            PllConfig::<f32>::default().pi_integral_gain
+        );
+    }
+
+    #[test]
+    fn loop_spec_is_validated() {
+        let config = PllConfig::from_loop_spec(0.0, 50.0, 20.0, 0.7);
+        assert_eq!(config.err(), Some(PllError::InvalidSampleRate));
+        let config = PllConfig::from_loop_spec(12000.0, -50.0, 20.0, 0.7);
+        assert_eq!(config.err(), Some(PllError::InvalidOmegaZero));
+    }
+}
@@ -0,0 +1,46 @@
+//! Transport delay quadrature signal generator
+
+use crate::Float;
+
+/// Quadrature signal from delaying the input by `N` samples
+///
+/// The delay is a 90° lag when `N = sample_rate / (4 · frequency)`, e.g. 60 at 12kHz and 50Hz.
+/// It is exact only at that frequency and passes harmonics straight through, unlike the
+/// [`Sogi`](crate::Sogi).
+#[derive(Clone, Debug)]
+pub struct QuadratureDelay<const N: usize, T = f32> {
+    buffer: [T; N],
+    index: usize,
+}
+
+impl<const N: usize, T: Float> QuadratureDelay<N, T> {
+    /// Create a new delay line filled with zeros
+    pub fn new() -> Self {
+        Self {
+            buffer: [T::ZERO; N],
+            index: 0,
+        }
+    }
+
+    /// Add a new sample
+    /// Returns (v, v delayed by N samples)
+    ///
+    /// Panics if `N` is 0
+    pub fn update(&mut self, v: T) -> (T, T) {
+        let delayed = core::mem::replace(&mut self.buffer[self.index], v);
+        self.index = (self.index + 1) % N;
+        (v, delayed)
+    }
+
+    /// Fill the delay line with zeros
+    pub fn reset(&mut self) {
+        self.buffer = [T::ZERO; N];
+        self.index = 0;
+    }
+}
+
+impl<const N: usize, T: Float> Default for QuadratureDelay<N, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,98 @@
+//! Dual SOGI for positive/negative sequence extraction
+
+use crate::{clarke, Float, PllConfig, PllResult, Sogi, SogiPll};
+
+/// Positive and negative sequence alpha/beta components
+#[derive(Clone, Debug)]
+pub struct DsogiOutput<T = f32> {
+    pub pos_alpha: T,
+    pub pos_beta: T,
+    pub neg_alpha: T,
+    pub neg_beta: T,
+}
+
+/// Dual SOGI, one SOGI on each of the alpha and beta axes
+#[derive(Clone, Debug)]
+pub struct Dsogi<T = f32> {
+    sogi_alpha: Sogi<T>,
+    sogi_beta: Sogi<T>,
+}
+
+impl<T: Float> Dsogi<T> {
+    /// Create a new DSOGI with a given k and sample time(used for the integrators)
+    pub fn new(k: T, sample_time: T) -> Self {
+        Self {
+            sogi_alpha: Sogi::new(k, sample_time),
+            sogi_beta: Sogi::new(k, sample_time),
+        }
+    }
+
+    /// Update the DSOGI with a new alpha/beta measurement
+    ///
+    /// The sequences are separated with the quadrature outputs `qα` and `qβ` of the SOGIs:
+    ///
+    /// ```text
+    /// pos_alpha = (α - qβ) / 2    neg_alpha = (α + qβ) / 2
+    /// pos_beta  = (qα + β) / 2    neg_beta  = (β - qα) / 2
+    /// ```
+    pub fn update(&mut self, alpha: T, beta: T, omega: T) -> DsogiOutput<T> {
+        let (alpha, q_alpha) = self.sogi_alpha.update(alpha, omega);
+        let (beta, q_beta) = self.sogi_beta.update(beta, omega);
+        let half = T::from_f32(0.5);
+
+        DsogiOutput {
+            pos_alpha: (alpha - q_beta) * half,
+            pos_beta: (q_alpha + beta) * half,
+            neg_alpha: (alpha + q_beta) * half,
+            neg_beta: (beta - q_alpha) * half,
+        }
+    }
+
+    /// Clear the state of both SOGIs
+    pub fn reset(&mut self) {
+        self.sogi_alpha.reset();
+        self.sogi_beta.reset();
+    }
+}
+
+/// Three-phase DSOGI-PLL locking onto the positive sequence
+#[derive(Clone, Debug)]
+pub struct DsogiPll<T = f32> {
+    dsogi: Dsogi<T>,
+    pll: SogiPll<T>,
+}
+
+impl<T: Float> DsogiPll<T> {
+    /// Create a new DSOGI-PLL with a given configuration
+    pub fn new(config: PllConfig<T>) -> DsogiPll<T> {
+        DsogiPll {
+            dsogi: Dsogi::new(config.sogi_k, config.sample_time),
+            pll: SogiPll::new(config),
+        }
+    }
+
+    /// Update the PLL with a new three-phase voltage measurement
+    /// Returns the result for the positive sequence and the separated sequences
+    pub fn update(&mut self, va: T, vb: T, vc: T) -> (PllResult<T>, DsogiOutput<T>) {
+        let scale = self.pll.config.input_scale;
+        let (alpha, beta) = clarke(va * scale, vb * scale, vc * scale);
+        let dt = self.pll.config.sample_time;
+        let omega = self.pll.next_omega(dt);
+        let sequences = self.dsogi.update(alpha, beta, omega);
+        let result = self
+            .pll
+            .track(sequences.pos_alpha, sequences.pos_beta, omega, dt);
+        (result, sequences)
+    }
+
+    /// Reset the PLL to its initial state, keeping the configuration
+    pub fn reset(&mut self) {
+        self.dsogi.reset();
+        self.pll.reset();
+    }
+
+    /// See [`SogiPll::is_locked`]
+    pub fn is_locked(&self) -> bool {
+        self.pll.is_locked()
+    }
+}
@@ -0,0 +1,258 @@
+//! Fixed-point SOGI-PLL for MCUs without an FPU
+//!
+//! The update path uses integer arithmetic only. Floats are used once, when converting
+//! the [`PllConfig`] into fixed-point coefficients.
+//!
+//! Formats:
+//!
+//! ```text
+//! voltages, omega (rad/s)  Q16.16 in i32, i.e. value * 65536
+//! theta                    u32 fraction of a turn, 2^32 = 2π
+//! integrator state         Q32.32 in i64
+//! ```
+//!
+//! Coefficients derived from the sample time are stored with 40 fractional bits,
+//! which keeps all products within i64 for sample times below about 4 ms.
+
+use crate::PllConfig;
+
+/// Number of fractional bits of the Q16.16 format
+pub const FRAC_BITS: u32 = 16;
+
+const ONE_Q16: f32 = (1u32 << FRAC_BITS) as f32;
+const ONE_Q32: f32 = 4_294_967_296.0;
+const ONE_Q40: f32 = 1_099_511_627_776.0;
+
+/// atan(2^-i) as a fraction of a turn
+const CORDIC_ATAN: [i32; 16] = [
+    536870912, 316933406, 167458907, 85004756, 42667331, 21354465, 10679838, 5340245, 2670163,
+    1335087, 667544, 333772, 166886, 83443, 41722, 20861,
+];
+
+/// CORDIC gain compensation for 16 iterations in Q2.30
+const CORDIC_K: i32 = 652032874;
+
+/// Convert a float to Q16.16
+fn to_q16(value: f32) -> i32 {
+    (value * ONE_Q16) as i32
+}
+
+/// Multiply two Q16.16 numbers
+fn mul_q16(a: i32, b: i32) -> i32 {
+    saturate((a as i64 * b as i64) >> FRAC_BITS)
+}
+
+fn saturate(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// Returns (sin, cos) in Q2.30 of an angle given as a fraction of a turn
+fn sin_cos(theta: u32) -> (i32, i32) {
+    // Bring the angle into -π/2..π/2, where CORDIC converges
+    let mut z = theta as i32;
+    let negate = !(-(1 << 30)..=(1 << 30)).contains(&z);
+    if negate {
+        z = z.wrapping_add(i32::MIN);
+    }
+
+    let mut x = CORDIC_K;
+    let mut y = 0;
+    for (i, atan) in CORDIC_ATAN.iter().enumerate() {
+        let (dx, dy) = (y >> i, x >> i);
+        if z >= 0 {
+            x -= dx;
+            y += dy;
+            z -= atan;
+        } else {
+            x += dx;
+            y -= dy;
+            z += atan;
+        }
+    }
+
+    if negate {
+        (-y, -x)
+    } else {
+        (y, x)
+    }
+}
+
+/// Fixed-point counterpart of the third order integrator
+#[derive(Clone, Debug)]
+struct ThirdOrderIntegratorFixed {
+    /// sample_time / 12 with 40 fractional bits
+    integrator_gain: i64,
+    z1: i64,
+    z2: i64,
+    z3: i64,
+}
+
+impl ThirdOrderIntegratorFixed {
+    fn new(integrator_gain: i64) -> Self {
+        Self {
+            integrator_gain,
+            z1: 0,
+            z2: 0,
+            z3: 0,
+        }
+    }
+
+    /// Update the integrator with a new Q16.16 input
+    fn update(&mut self, x: i32) {
+        self.z3 = self.z2;
+        self.z2 = self.z1;
+        self.z1 += (x as i64 * self.integrator_gain) >> 24;
+    }
+
+    /// Get the current value of the integrator in Q16.16
+    fn value(&self) -> i32 {
+        saturate((self.z1 * 23 - self.z2 * 16 + self.z3 * 5) >> 16)
+    }
+
+    fn reset(&mut self) {
+        self.z1 = 0;
+        self.z2 = 0;
+        self.z3 = 0;
+    }
+}
+
+/// Fixed-point SOGI, see [`Sogi`](crate::Sogi)
+#[derive(Clone, Debug)]
+pub struct SogiFixed {
+    /// Q16.16
+    k: i32,
+    integrator_1: ThirdOrderIntegratorFixed,
+    integrator_2: ThirdOrderIntegratorFixed,
+}
+
+impl SogiFixed {
+    /// Create a new SOGI with a given k and sample time(used for the integrators)
+    pub fn new(k: f32, sample_time: f32) -> Self {
+        let integrator_gain = (sample_time / 12.0 * ONE_Q40) as i64;
+        Self {
+            k: to_q16(k),
+            integrator_1: ThirdOrderIntegratorFixed::new(integrator_gain),
+            integrator_2: ThirdOrderIntegratorFixed::new(integrator_gain),
+        }
+    }
+
+    /// Update SOGI with a new Q16.16 voltage measurement and omega
+    /// Returns (v_alpha, v_beta) in Q16.16
+    pub fn update(&mut self, v: i32, omega: i32) -> (i32, i32) {
+        let v_alpha = self.integrator_1.value();
+        let v_beta = self.integrator_2.value();
+
+        let error = mul_q16(v.saturating_sub(v_alpha), self.k).saturating_sub(v_beta);
+        let integrator_1_in = mul_q16(error, omega);
+        let integrator_2_in = mul_q16(v_alpha, omega);
+
+        self.integrator_1.update(integrator_1_in);
+        self.integrator_2.update(integrator_2_in);
+
+        (v_alpha, v_beta)
+    }
+
+    /// Clear the state of both integrators
+    pub fn reset(&mut self) {
+        self.integrator_1.reset();
+        self.integrator_2.reset();
+    }
+}
+
+/// Rotate alpha/beta into the dq frame at angle theta
+/// Returns (d, q) in Q16.16
+fn alpha_beta_to_dq(alpha: i32, beta: i32, theta: u32) -> (i32, i32) {
+    let (sin, cos) = sin_cos(theta);
+    let (alpha, beta, sin, cos) = (alpha as i64, beta as i64, sin as i64, cos as i64);
+    let d = (alpha * cos + beta * sin) >> 30;
+    let q = (beta * cos - alpha * sin) >> 30;
+    (saturate(d), saturate(q))
+}
+
+/// Result returned by the fixed-point SOGI-PLL
+#[derive(Clone, Debug)]
+pub struct PllResultFixed {
+    /// Q16.16
+    pub v_alpha: i32,
+    /// Q16.16
+    pub v_beta: i32,
+    /// Q16.16
+    pub v_d: i32,
+    /// Q16.16 rad/s
+    pub omega: i32,
+    /// Fraction of a turn, 2^32 = 2π
+    pub theta: u32,
+}
+
+/// Fixed-point SOGI-PLL, see [`SogiPll`](crate::SogiPll)
+///
+/// Uses `sample_time`, `sogi_k`, `pi_proportional_gain`, `pi_integral_gain` and `omega_zero`
+/// from the configuration.
+#[derive(Clone, Debug)]
+pub struct SogiPllFixed {
+    sogi: SogiFixed,
+    /// Q16.16
+    omega_zero: i32,
+    /// Q16.16
+    kp: i32,
+    /// 32 fractional bits
+    ki: i64,
+    /// sample_time with 40 fractional bits
+    sample_time: i64,
+    /// sample_time / 2π with 40 fractional bits
+    phase_gain: i64,
+    /// Q32.32
+    pi_integral: i64,
+    /// Q16.16
+    pi_value: i32,
+    z1: u32,
+}
+
+impl SogiPllFixed {
+    /// Create a new fixed-point SOGI-PLL with a given configuration
+    pub fn new(config: PllConfig) -> SogiPllFixed {
+        SogiPllFixed {
+            sogi: SogiFixed::new(config.sogi_k, config.sample_time),
+            omega_zero: to_q16(config.omega_zero),
+            kp: to_q16(config.pi_proportional_gain),
+            ki: (config.pi_integral_gain * ONE_Q32) as i64,
+            sample_time: (config.sample_time * ONE_Q40) as i64,
+            phase_gain: (config.sample_time / core::f32::consts::TAU * ONE_Q40) as i64,
+            pi_integral: 0,
+            pi_value: 0,
+            z1: 0,
+        }
+    }
+
+    /// Update the PLL with a new Q16.16 voltage measurement
+    pub fn update(&mut self, v: i32) -> PllResultFixed {
+        let omega = self.pi_value.saturating_add(self.omega_zero);
+        let (v_alpha, v_beta) = self.sogi.update(v, omega);
+
+        let (v_d, q) = alpha_beta_to_dq(v_alpha, v_beta, self.z1);
+
+        // Q16.16 * 40 fractional bits >> 24 = fraction of a turn in 32 bits
+        let phase_increment = (omega as i64 * self.phase_gain) >> 24;
+        self.z1 = self.z1.wrapping_add(phase_increment as u32);
+
+        self.pi_integral += (self.pi_value as i64 * self.sample_time) >> 24;
+        let integral_term = (self.pi_integral >> 16).saturating_mul(self.ki) >> 32;
+        self.pi_value = mul_q16(q, self.kp).saturating_add(saturate(integral_term));
+
+        PllResultFixed {
+            v_alpha,
+            v_beta,
+            v_d,
+            omega,
+            theta: self.z1,
+        }
+    }
+
+    /// Reset the PLL to its initial state, keeping the configuration
+    pub fn reset(&mut self) {
+        self.sogi.reset();
+        self.pi_integral = 0;
+        self.pi_value = 0;
+        self.z1 = 0;
+    }
+}
@@ -0,0 +1,185 @@
+//! SOGI frequency-locked loop
+
+use crate::float::accurate_sqrt;
+use crate::{rotation_between, wrap_difference, Float, FrequencyAlarm, PllResult, Sogi};
+
+/// Configuration for the SOGI-FLL
+#[derive(Clone, Debug)]
+pub struct FllConfig<T = f32> {
+    pub sample_time: T,
+    pub sogi_k: T,
+    /// FLL gain
+    pub gamma: T,
+    pub omega_zero: T,
+}
+
+/// SOGI-FLL implementation
+///
+/// Instead of a q-axis PI loop the frequency is adapted directly from the SOGI error
+/// `ε = v - v_alpha`:
+///
+/// ```text
+/// dω/dt = -γ · ε · v_beta / (v_alpha² + v_beta²)
+/// ```
+///
+/// The phase is taken from the SOGI outputs as `atan2(v_beta, v_alpha)`. `zero_crossing`
+/// marks theta passing 3π/2, the rising zero crossing of the input, like the default
+/// `zero_crossing_angle` of [`SogiPll`](crate::SogiPll).
+#[derive(Clone, Debug)]
+pub struct SogiFll<T = f32> {
+    config: FllConfig<T>,
+    sogi: Sogi<T>,
+    omega: T,
+    /// omega reported for the previous sample
+    omega_prev: T,
+    theta: T,
+    revolutions: i32,
+    /// (v_alpha, v_beta) of the previous sample
+    sogi_prev: (T, T),
+}
+
+impl<T: Float> SogiFll<T> {
+    /// Create a new SOGI-FLL with a given configuration
+    pub fn new(config: FllConfig<T>) -> SogiFll<T> {
+        let sogi = Sogi::new(config.sogi_k, config.sample_time);
+
+        SogiFll {
+            omega: config.omega_zero,
+            omega_prev: config.omega_zero,
+            theta: T::ZERO,
+            revolutions: 0,
+            sogi_prev: (T::ZERO, T::ZERO),
+            config,
+            sogi,
+        }
+    }
+
+    /// Update the FLL with a new voltage measurement
+    pub fn update(&mut self, v: T) -> PllResult<T> {
+        let omega = self.omega;
+        let rocof_hz_per_s = (omega - self.omega_prev) / (T::PI2 * self.config.sample_time);
+        self.omega_prev = omega;
+        let (v_alpha, v_beta) = self.sogi.update(v, omega);
+
+        let magnitude_squared = v_alpha * v_alpha + v_beta * v_beta;
+        // Hold the frequency until the SOGI has some output to normalise by
+        if magnitude_squared > T::ZERO {
+            let error = v - v_alpha;
+            let omega_dot = -self.config.gamma * error * v_beta / magnitude_squared;
+            self.omega += omega_dot * self.config.sample_time;
+        }
+
+        let mut theta = v_beta.atan2(v_alpha);
+        if theta < T::ZERO {
+            theta += T::PI2;
+        }
+        // A jump of more than half a turn is a wrap
+        if theta < self.theta - T::PI {
+            self.revolutions = self.revolutions.wrapping_add(1);
+        } else if theta > self.theta + T::PI {
+            self.revolutions = self.revolutions.wrapping_sub(1);
+        }
+        let mut distance = T::from_f32(1.5) * T::PI - self.theta;
+        if distance < T::ZERO {
+            distance += T::PI2;
+        }
+        let zero_crossing = distance > T::ZERO && distance <= wrap_difference(theta - self.theta);
+        let omega_from_phase =
+            rotation_between(self.sogi_prev, (v_alpha, v_beta)) / self.config.sample_time;
+        self.sogi_prev = (v_alpha, v_beta);
+        self.theta = theta;
+
+        let magnitude = accurate_sqrt(magnitude_squared);
+        let (sin_theta, cos_theta) = if magnitude > T::ZERO {
+            (v_beta / magnitude, v_alpha / magnitude)
+        } else {
+            (T::ZERO, T::ONE)
+        };
+
+        PllResult {
+            v_alpha,
+            v_beta,
+            v_d: magnitude,
+            // theta is taken straight from alpha/beta, so there is no phase error
+            q: T::ZERO,
+            omega,
+            theta,
+            sin_theta,
+            cos_theta,
+            revolutions: self.revolutions,
+            rocof_hz_per_s,
+            phase_step: omega * self.config.sample_time,
+            zero_crossing,
+            // FllConfig has no alarm limits
+            frequency_alarm: FrequencyAlarm::Nominal,
+            omega_from_phase,
+            // theta follows alpha/beta directly
+            phase_jump: false,
+        }
+    }
+
+    /// Reset the FLL to its initial state, keeping the configuration
+    pub fn reset(&mut self) {
+        self.sogi.reset();
+        self.omega = self.config.omega_zero;
+        self.omega_prev = self.config.omega_zero;
+        self.theta = T::ZERO;
+        self.revolutions = 0;
+        self.sogi_prev = (T::ZERO, T::ZERO);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::f64::consts::TAU;
+
+    const SAMPLE_RATE: f64 = 12000.0;
+
+    fn fll() -> SogiFll {
+        SogiFll::new(FllConfig {
+            sample_time: (1.0 / SAMPLE_RATE) as f32,
+            sogi_k: 1.0,
+            gamma: 50.0,
+            omega_zero: (TAU * 50.0) as f32,
+        })
+    }
+
+    /// Phase of a 50Hz cosine at sample `n`
+    fn phase(n: u32) -> f64 {
+        TAU * 50.0 * n as f64 / SAMPLE_RATE
+    }
+
+    #[test]
+    fn sin_cos_are_normalised() {
+        let mut fll = fll();
+        for n in 0..12000 {
+            let result = fll.update(1.5 * phase(n).cos() as f32);
+            if n > 2400 {
+                let norm =
+                    result.sin_theta * result.sin_theta + result.cos_theta * result.cos_theta;
+                assert!((norm - 1.0).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_crossing_marks_the_rising_edge() {
+        use core::f64::consts::PI;
+
+        let mut fll = fll();
+        let mut crossings = 0;
+        for n in 0..24000 {
+            let result = fll.update(phase(n).cos() as f32);
+            if n >= 12000 && result.zero_crossing {
+                crossings += 1;
+                // Within a sample of the input phase reaching 3π/2
+                let offset = (phase(n) - 1.5 * PI + PI).rem_euclid(TAU) - PI;
+                assert!(offset.abs() < TAU * 50.0 / SAMPLE_RATE);
+            }
+        }
+        assert_eq!(crossings, 50);
+    }
+}
@@ -0,0 +1,202 @@
+//! Floating point abstraction used by the PLL
+
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+
+/// Floating point operations required by the PLL
+///
+/// Implemented for `f32` using `micromath` and, with the `f64` feature, for `f64` using `libm`.
+/// The `libm` feature switches the `f32` implementation to `libm` as well, trading code size
+/// and speed for full precision, and the `lut` feature replaces the `f32` `sin_cos` with a
+/// constant-time lookup table.
+///
+/// Other number types can be used by implementing this trait. There is no blanket
+/// implementation for `num_traits::Float`, as it would overlap with the `f32` and `f64` ones.
+pub trait Float:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const PI: Self;
+    /// 2π
+    const PI2: Self;
+    /// 1 / 2π
+    const FRAC_1_2PI: Self;
+    const FRAC_1_SQRT_2: Self;
+    const INFINITY: Self;
+
+    /// Convert an `f32` constant into this type
+    fn from_f32(value: f32) -> Self;
+
+    /// Convert into an `f32`, rounding if needed
+    fn to_f32(self) -> f32;
+
+    /// Convert an integer such as a sample count, rounding if needed
+    ///
+    /// The default goes through `f32` and is exact up to 2^24, types with a wider mantissa
+    /// should override it.
+    fn from_i64(value: i64) -> Self {
+        Self::from_f32(value as f32)
+    }
+
+    /// Returns (sin, cos)
+    fn sin_cos(self) -> (Self, Self);
+
+    fn sqrt(self) -> Self;
+
+    /// Four quadrant arctangent of `self / x`, 0 when both are zero
+    fn atan2(self, x: Self) -> Self;
+
+    /// Floating point remainder, same semantics as `%`
+    fn rem(self, rhs: Self) -> Self;
+
+    fn abs(self) -> Self;
+
+    /// Neither infinite nor NaN
+    fn is_finite(self) -> bool;
+
+    /// Restrict the value to `min..=max`
+    fn clamp(self, min: Self, max: Self) -> Self;
+}
+
+/// `sqrt` refined with two Newton steps, for readings like RMS where the `micromath`
+/// approximation is too coarse, e.g. 0.75 for `sqrt(0.5)`
+pub(crate) fn accurate_sqrt<T: Float>(x: T) -> T {
+    let mut y = x.sqrt();
+    if y > T::ZERO && x.is_finite() {
+        let half = T::from_f32(0.5);
+        y = half * (y + x / y);
+        y = half * (y + x / y);
+    }
+    y
+}
+
+impl Float for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+    const PI: Self = core::f32::consts::PI;
+    const PI2: Self = core::f32::consts::PI * 2.0;
+    const FRAC_1_2PI: Self = 1.0 / Self::PI2;
+    const FRAC_1_SQRT_2: Self = core::f32::consts::FRAC_1_SQRT_2;
+    const INFINITY: Self = f32::INFINITY;
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[cfg(not(any(feature = "libm", feature = "lut")))]
+    fn sin_cos(self) -> (Self, Self) {
+        micromath::F32Ext::sin_cos(self)
+    }
+
+    #[cfg(all(feature = "libm", not(feature = "lut")))]
+    fn sin_cos(self) -> (Self, Self) {
+        (libm::sinf(self), libm::cosf(self))
+    }
+
+    #[cfg(feature = "lut")]
+    fn sin_cos(self) -> (Self, Self) {
+        crate::lut::sin_cos(self)
+    }
+
+    #[cfg(not(feature = "libm"))]
+    fn sqrt(self) -> Self {
+        micromath::F32Ext::sqrt(self)
+    }
+
+    #[cfg(feature = "libm")]
+    fn sqrt(self) -> Self {
+        libm::sqrtf(self)
+    }
+
+    #[cfg(not(feature = "libm"))]
+    fn atan2(self, x: Self) -> Self {
+        // micromath returns NaN here, e.g. for the SOGI outputs on the first sample
+        if self == 0.0 && x == 0.0 {
+            return 0.0;
+        }
+        micromath::F32Ext::atan2(self, x)
+    }
+
+    #[cfg(feature = "libm")]
+    fn atan2(self, x: Self) -> Self {
+        libm::atan2f(self, x)
+    }
+
+    fn rem(self, rhs: Self) -> Self {
+        self % rhs
+    }
+
+    fn abs(self) -> Self {
+        micromath::F32Ext::abs(self)
+    }
+
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+
+    fn clamp(self, min: Self, max: Self) -> Self {
+        f32::clamp(self, min, max)
+    }
+}
+
+#[cfg(feature = "f64")]
+impl Float for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+    const PI: Self = core::f64::consts::PI;
+    const PI2: Self = core::f64::consts::PI * 2.0;
+    const FRAC_1_2PI: Self = 1.0 / Self::PI2;
+    const FRAC_1_SQRT_2: Self = core::f64::consts::FRAC_1_SQRT_2;
+    const INFINITY: Self = f64::INFINITY;
+
+    fn from_f32(value: f32) -> Self {
+        value as f64
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    fn from_i64(value: i64) -> Self {
+        value as f64
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (libm::sin(self), libm::cos(self))
+    }
+
+    fn sqrt(self) -> Self {
+        libm::sqrt(self)
+    }
+
+    fn atan2(self, x: Self) -> Self {
+        libm::atan2(self, x)
+    }
+
+    fn rem(self, rhs: Self) -> Self {
+        self % rhs
+    }
+
+    fn abs(self) -> Self {
+        libm::fabs(self)
+    }
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+
+    fn clamp(self, min: Self, max: Self) -> Self {
+        f64::clamp(self, min, max)
+    }
+}
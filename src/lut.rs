@@ -0,0 +1,310 @@
+//! Sine lookup table for constant-time sin/cos
+//!
+//! Used for the `f32` [`Float::sin_cos`](crate::Float::sin_cos) with the `lut` feature. The
+//! table is a `static`, so a single copy lives in flash and costs no RAM. Linear interpolation between
+//! 256 entries per turn gives a worst-case error of about 7.6e-5 in sin and cos, i.e. below
+//! 0.1 mrad of angle error.
+
+/// Entries per turn, a power of two
+const SIZE: usize = 256;
+
+/// sin(2π·i / SIZE), with a repeated first entry so interpolation never wraps
+static SINE: [f32; SIZE + 1] = [
+    0.0,
+    0.024541229,
+    0.049067676,
+    0.07356457,
+    0.09801714,
+    0.12241068,
+    0.14673047,
+    0.17096189,
+    0.19509032,
+    0.21910124,
+    0.24298018,
+    0.26671275,
+    0.29028466,
+    0.31368175,
+    0.33688986,
+    0.35989505,
+    0.38268343,
+    0.4052413,
+    0.42755508,
+    0.44961134,
+    0.47139674,
+    0.4928982,
+    0.51410276,
+    0.53499764,
+    0.55557024,
+    0.57580817,
+    0.5956993,
+    0.6152316,
+    0.6343933,
+    0.65317285,
+    0.671559,
+    0.68954057,
+    0.70710677,
+    0.7242471,
+    0.7409511,
+    0.7572088,
+    0.77301043,
+    0.7883464,
+    0.8032075,
+    0.8175848,
+    0.8314696,
+    0.8448536,
+    0.8577286,
+    0.87008697,
+    0.8819213,
+    0.8932243,
+    0.9039893,
+    0.9142098,
+    0.9238795,
+    0.9329928,
+    0.94154406,
+    0.94952816,
+    0.95694035,
+    0.96377605,
+    0.97003126,
+    0.9757021,
+    0.98078525,
+    0.98527765,
+    0.9891765,
+    0.99247956,
+    0.9951847,
+    0.99729043,
+    0.99879545,
+    0.9996988,
+    1.0,
+    0.9996988,
+    0.99879545,
+    0.99729043,
+    0.9951847,
+    0.99247956,
+    0.9891765,
+    0.98527765,
+    0.98078525,
+    0.9757021,
+    0.97003126,
+    0.96377605,
+    0.95694035,
+    0.94952816,
+    0.94154406,
+    0.9329928,
+    0.9238795,
+    0.9142098,
+    0.9039893,
+    0.8932243,
+    0.8819213,
+    0.87008697,
+    0.8577286,
+    0.8448536,
+    0.8314696,
+    0.8175848,
+    0.8032075,
+    0.7883464,
+    0.77301043,
+    0.7572088,
+    0.7409511,
+    0.7242471,
+    0.70710677,
+    0.68954057,
+    0.671559,
+    0.65317285,
+    0.6343933,
+    0.6152316,
+    0.5956993,
+    0.57580817,
+    0.55557024,
+    0.53499764,
+    0.51410276,
+    0.4928982,
+    0.47139674,
+    0.44961134,
+    0.42755508,
+    0.4052413,
+    0.38268343,
+    0.35989505,
+    0.33688986,
+    0.31368175,
+    0.29028466,
+    0.26671275,
+    0.24298018,
+    0.21910124,
+    0.19509032,
+    0.17096189,
+    0.14673047,
+    0.12241068,
+    0.09801714,
+    0.07356457,
+    0.049067676,
+    0.024541229,
+    0.0,
+    -0.024541229,
+    -0.049067676,
+    -0.07356457,
+    -0.09801714,
+    -0.12241068,
+    -0.14673047,
+    -0.17096189,
+    -0.19509032,
+    -0.21910124,
+    -0.24298018,
+    -0.26671275,
+    -0.29028466,
+    -0.31368175,
+    -0.33688986,
+    -0.35989505,
+    -0.38268343,
+    -0.4052413,
+    -0.42755508,
+    -0.44961134,
+    -0.47139674,
+    -0.4928982,
+    -0.51410276,
+    -0.53499764,
+    -0.55557024,
+    -0.57580817,
+    -0.5956993,
+    -0.6152316,
+    -0.6343933,
+    -0.65317285,
+    -0.671559,
+    -0.68954057,
+    -0.70710677,
+    -0.7242471,
+    -0.7409511,
+    -0.7572088,
+    -0.77301043,
+    -0.7883464,
+    -0.8032075,
+    -0.8175848,
+    -0.8314696,
+    -0.8448536,
+    -0.8577286,
+    -0.87008697,
+    -0.8819213,
+    -0.8932243,
+    -0.9039893,
+    -0.9142098,
+    -0.9238795,
+    -0.9329928,
+    -0.94154406,
+    -0.94952816,
+    -0.95694035,
+    -0.96377605,
+    -0.97003126,
+    -0.9757021,
+    -0.98078525,
+    -0.98527765,
+    -0.9891765,
+    -0.99247956,
+    -0.9951847,
+    -0.99729043,
+    -0.99879545,
+    -0.9996988,
+    -1.0,
+    -0.9996988,
+    -0.99879545,
+    -0.99729043,
+    -0.9951847,
+    -0.99247956,
+    -0.9891765,
+    -0.98527765,
+    -0.98078525,
+    -0.9757021,
+    -0.97003126,
+    -0.96377605,
+    -0.95694035,
+    -0.94952816,
+    -0.94154406,
+    -0.9329928,
+    -0.9238795,
+    -0.9142098,
+    -0.9039893,
+    -0.8932243,
+    -0.8819213,
+    -0.87008697,
+    -0.8577286,
+    -0.8448536,
+    -0.8314696,
+    -0.8175848,
+    -0.8032075,
+    -0.7883464,
+    -0.77301043,
+    -0.7572088,
+    -0.7409511,
+    -0.7242471,
+    -0.70710677,
+    -0.68954057,
+    -0.671559,
+    -0.65317285,
+    -0.6343933,
+    -0.6152316,
+    -0.5956993,
+    -0.57580817,
+    -0.55557024,
+    -0.53499764,
+    -0.51410276,
+    -0.4928982,
+    -0.47139674,
+    -0.44961134,
+    -0.42755508,
+    -0.4052413,
+    -0.38268343,
+    -0.35989505,
+    -0.33688986,
+    -0.31368175,
+    -0.29028466,
+    -0.26671275,
+    -0.24298018,
+    -0.21910124,
+    -0.19509032,
+    -0.17096189,
+    -0.14673047,
+    -0.12241068,
+    -0.09801714,
+    -0.07356457,
+    -0.049067676,
+    -0.024541229,
+    0.0,
+];
+
+/// Returns (sin, cos) of an angle in radians by interpolating [`SINE`]
+pub(crate) fn sin_cos(theta: f32) -> (f32, f32) {
+    let position = theta * (SIZE as f32 / core::f32::consts::TAU);
+    // Round towards negative infinity so the fraction is always in 0..1
+    let mut whole = position as i32;
+    if whole as f32 > position {
+        whole -= 1;
+    }
+    let fraction = position - whole as f32;
+
+    let sin_index = whole as usize & (SIZE - 1);
+    let cos_index = (sin_index + SIZE / 4) & (SIZE - 1);
+    (
+        interpolate(sin_index, fraction),
+        interpolate(cos_index, fraction),
+    )
+}
+
+fn interpolate(index: usize, fraction: f32) -> f32 {
+    let low = SINE[index];
+    low + (SINE[index + 1] - low) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    #[test]
+    fn worst_case_error() {
+        let mut largest: f32 = 0.0;
+        for i in -20000..20000 {
+            let theta = i as f32 * 0.001;
+            let (sin, cos) = super::sin_cos(theta);
+            largest = largest.max((sin - theta.sin()).abs());
+            largest = largest.max((cos - theta.cos()).abs());
+        }
+        assert!(largest < 8e-5);
+    }
+}
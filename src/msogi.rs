@@ -0,0 +1,185 @@
+//! Multiple SOGI harmonic bank
+
+use crate::float::accurate_sqrt;
+use crate::{Float, Sogi};
+
+/// Bank of `N` SOGIs tuned to harmonics of the fundamental
+///
+/// Each SOGI sees the input minus the in-phase outputs of all the other SOGIs,
+/// so every harmonic is removed from the others' inputs.
+///
+/// The cross-cancellation makes the bank less stable than a single SOGI. With one `k` for
+/// all orders passed to [`Msogi::with_gains`] the higher orders get a bandwidth of
+/// `k · h · ω`, and at 12kHz the bank diverges for `k = √2` with orders `[1, 3, 5, 7]`.
+/// [`Msogi::default_gains`] uses `k / h` instead, giving every order the same absolute
+/// bandwidth. It stays stable up to at least `k = √2`, where every order settles within four
+/// cycles and the 7th reads about 2% low, 3% at `k = 1`.
+#[derive(Clone, Debug)]
+pub struct Msogi<const N: usize, T = f32> {
+    sogis: [Sogi<T>; N],
+    orders: [u8; N],
+    outputs: [(T, T); N],
+}
+
+impl<const N: usize, T: Float> Msogi<N, T> {
+    /// Create a new harmonic bank with a given k, sample time and harmonic orders,
+    /// e.g. `[1, 3, 5, 7]`
+    ///
+    /// Uses [`Msogi::default_gains`], so `k` is the gain of the fundamental.
+    pub fn new(k: T, sample_time: T, orders: [u8; N]) -> Self {
+        Self::with_gains(Self::default_gains(k, &orders), sample_time, orders)
+    }
+
+    /// Create a new harmonic bank with one k per harmonic order, see
+    /// [`Msogi::default_gains`]
+    pub fn with_gains(ks: [T; N], sample_time: T, orders: [u8; N]) -> Self {
+        Self {
+            sogis: core::array::from_fn(|i| Sogi::new(ks[i], sample_time)),
+            orders,
+            outputs: [(T::ZERO, T::ZERO); N],
+        }
+    }
+
+    /// Recommended gains `k / h` for the given orders, an equal absolute bandwidth of
+    /// `k · ω` for every harmonic
+    pub fn default_gains(k: T, orders: &[u8; N]) -> [T; N] {
+        core::array::from_fn(|i| k / T::from_f32(orders[i].max(1) as f32))
+    }
+
+    /// Update the bank with a new voltage measurement and the fundamental omega
+    /// Returns (v_alpha, v_beta) for each configured harmonic
+    pub fn update(&mut self, v: T, omega: T) -> [(T, T); N] {
+        // The in-phase outputs for this sample are already held by the integrators, so the
+        // cancellation uses them rather than the previous sample's outputs
+        let mut current = [T::ZERO; N];
+        let mut total = T::ZERO;
+        for (v_alpha, sogi) in current.iter_mut().zip(&self.sogis) {
+            *v_alpha = sogi.outputs().0;
+            total += *v_alpha;
+        }
+
+        for (i, &v_alpha) in current.iter().enumerate() {
+            let others = total - v_alpha;
+            let harmonic_omega = omega * T::from_f32(self.orders[i] as f32);
+            self.outputs[i] = self.sogis[i].update(v - others, harmonic_omega);
+        }
+
+        self.outputs
+    }
+
+    /// Total harmonic distortion from the most recent outputs
+    ///
+    /// Computed as `sqrt(Σ harmonic_rms²) / fundamental_rms`, where the fundamental is the
+    /// order 1 SOGI and the sum covers every other configured order. Harmonics that are not
+    /// in the bank are not included. Returns `None` if order 1 is not configured or its
+    /// amplitude is zero.
+    pub fn thd(&self) -> Option<T> {
+        let mut fundamental = None;
+        let mut harmonics = T::ZERO;
+        for (&order, &(v_alpha, v_beta)) in self.orders.iter().zip(&self.outputs) {
+            let rms = T::FRAC_1_SQRT_2 * accurate_sqrt(v_alpha * v_alpha + v_beta * v_beta);
+            if order == 1 {
+                fundamental = Some(rms);
+            } else {
+                harmonics += rms * rms;
+            }
+        }
+
+        fundamental
+            .filter(|&rms| rms > T::ZERO)
+            .map(|rms| accurate_sqrt(harmonics) / rms)
+    }
+
+    /// RMS amplitude of a harmonic order from the most recent outputs, `None` if the order
+    /// is not in the bank
+    pub fn harmonic_rms(&self, order: u8) -> Option<T> {
+        self.orders.iter().position(|&o| o == order).map(|i| {
+            let (v_alpha, v_beta) = self.outputs[i];
+            T::FRAC_1_SQRT_2 * accurate_sqrt(v_alpha * v_alpha + v_beta * v_beta)
+        })
+    }
+
+    /// Change the gain of the SOGI for `orders()[index]`, keeping its state
+    ///
+    /// Panics if `index >= N`
+    pub fn set_k(&mut self, index: usize, k: T) {
+        self.sogis[index].set_k(k);
+    }
+
+    /// Harmonic orders tracked by the bank
+    pub fn orders(&self) -> &[u8; N] {
+        &self.orders
+    }
+
+    /// Clear the state of all SOGIs
+    pub fn reset(&mut self) {
+        for sogi in &mut self.sogis {
+            sogi.reset();
+        }
+        self.outputs = [(T::ZERO, T::ZERO); N];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::f64::consts::TAU;
+
+    const SAMPLE_RATE: f64 = 12000.0;
+    const ORDERS: [u8; 4] = [1, 3, 5, 7];
+
+    /// 50Hz fundamental of unit amplitude with 20%, 10% and 5% of the 3rd, 5th and 7th
+    fn distorted(n: u32) -> f32 {
+        let phase = TAU * 50.0 * n as f64 / SAMPLE_RATE;
+        let v = phase.cos() + 0.2 * (3.0 * phase).cos() + 0.1 * (5.0 * phase).cos();
+        (v + 0.05 * (7.0 * phase).cos()) as f32
+    }
+
+    /// Largest |v_alpha| of any order over `samples`
+    fn peak_output<const N: usize>(bank: &mut Msogi<N>, samples: u32) -> f32 {
+        let omega = (TAU * 50.0) as f32;
+        let mut peak: f32 = 0.0;
+        for n in 0..samples {
+            for (v_alpha, _) in bank.update(distorted(n), omega) {
+                peak = peak.max(v_alpha.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn new_is_stable_at_root_two() {
+        let k = core::f32::consts::SQRT_2;
+        let mut bank = Msogi::new(k, 1.0 / SAMPLE_RATE as f32, ORDERS);
+        assert!(peak_output(&mut bank, 12000) < 2.0);
+    }
+
+    #[test]
+    fn equal_gains_diverge_at_root_two() {
+        let k = core::f32::consts::SQRT_2;
+        let mut bank = Msogi::with_gains([k; 4], 1.0 / SAMPLE_RATE as f32, ORDERS);
+        let peak = peak_output(&mut bank, 12000);
+        assert!(peak.is_nan() || peak > 1e6);
+    }
+
+    #[test]
+    fn new_converges_to_every_harmonic() {
+        let k = core::f32::consts::SQRT_2;
+        let mut bank = Msogi::new(k, 1.0 / SAMPLE_RATE as f32, ORDERS);
+        let omega = (TAU * 50.0) as f32;
+        for n in 0..12000 {
+            bank.update(distorted(n), omega);
+            if n < 960 {
+                continue;
+            }
+            for (order, amplitude) in ORDERS.into_iter().zip([1.0, 0.2, 0.1, 0.05]) {
+                let measured = bank.harmonic_rms(order).unwrap() * core::f32::consts::SQRT_2;
+                assert!((measured / amplitude - 1.0).abs() < 0.025);
+            }
+        }
+        let thd = (0.2f32 * 0.2 + 0.1 * 0.1 + 0.05 * 0.05).sqrt();
+        assert!((bank.thd().unwrap() / thd - 1.0).abs() < 0.005);
+    }
+}
@@ -0,0 +1,92 @@
+//! Several single-phase PLLs processed together
+
+use crate::{Float, PllConfig, PllResult, SogiPll};
+
+/// `N` independent SOGI-PLLs sharing one configuration
+///
+/// With `shared_omega` the frequency is estimated by channel 0. Every other channel uses that
+/// estimate in place of `omega_zero`, so its PI loop only corrects the residual phase error
+/// and the channels follow channel 0's frequency while keeping their own angles.
+#[derive(Clone, Debug)]
+pub struct MultiPll<const N: usize, T = f32> {
+    plls: [SogiPll<T>; N],
+    shared_omega: bool,
+    omega_zero: T,
+    /// Largest `frequency_spread` before `spread_alarm` is raised
+    spread_limit: T,
+}
+
+impl<const N: usize, T: Float> MultiPll<N, T> {
+    /// Create `N` channels with a given configuration
+    ///
+    /// Panics if the configuration is invalid, see [`SogiPll::try_new`]
+    pub fn new(config: PllConfig<T>, shared_omega: bool) -> Self {
+        Self {
+            omega_zero: config.omega_zero,
+            plls: core::array::from_fn(|_| SogiPll::new(config.clone())),
+            shared_omega,
+            spread_limit: T::INFINITY,
+        }
+    }
+
+    /// Update every channel with its new voltage measurement
+    pub fn update(&mut self, inputs: &[T; N]) -> [PllResult<T>; N] {
+        let mut omega = self.omega_zero;
+        // from_fn visits the channels in order, so channel 0 runs first
+        core::array::from_fn(|i| {
+            let pll = &mut self.plls[i];
+            if self.shared_omega && i > 0 {
+                pll.config.omega_zero = omega;
+            }
+            let result = pll.update(inputs[i]);
+            if i == 0 {
+                omega = result.omega;
+            }
+            result
+        })
+    }
+
+    /// Difference between the highest and the lowest channel omega in rad/s
+    ///
+    /// Channels on the same grid should agree, so a large spread points at a faulty sensor.
+    pub fn frequency_spread(&self) -> T {
+        let Some(first) = self.plls.first() else {
+            return T::ZERO;
+        };
+        let (mut min, mut max) = (first.omega_filtered, first.omega_filtered);
+        for pll in &self.plls[1..] {
+            if pll.omega_filtered < min {
+                min = pll.omega_filtered;
+            } else if pll.omega_filtered > max {
+                max = pll.omega_filtered;
+            }
+        }
+        max - min
+    }
+
+    /// Set the `frequency_spread` in rad/s above which [`MultiPll::spread_alarm`] is raised,
+    /// infinite by default
+    pub fn set_spread_limit(&mut self, limit: T) {
+        self.spread_limit = limit;
+    }
+
+    /// Whether the channels disagree by more than the spread limit
+    pub fn spread_alarm(&self) -> bool {
+        self.frequency_spread() > self.spread_limit
+    }
+
+    /// The PLL of a single channel
+    ///
+    /// Panics if `channel >= N`
+    pub fn channel(&self, channel: usize) -> &SogiPll<T> {
+        &self.plls[channel]
+    }
+
+    /// Reset every channel to its initial state, keeping the configuration
+    pub fn reset(&mut self) {
+        for pll in &mut self.plls {
+            pll.config.omega_zero = self.omega_zero;
+            pll.reset();
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Second order notch filter
+
+use crate::Float;
+
+/// Biquad notch removing a single frequency, e.g. a fixed interharmonic ahead of the PLL
+///
+/// Coefficients follow the RBJ audio EQ cookbook with `w0 = 2π · frequency · sample_time`:
+///
+/// ```text
+/// H(z) = (1 - 2cos(w0)·z⁻¹ + z⁻²) / ((1 + α) - 2cos(w0)·z⁻¹ + (1 - α)·z⁻²),  α = sin(w0) / 2Q
+/// ```
+///
+/// A higher `q` gives a narrower notch that takes longer to settle.
+#[derive(Clone, Debug)]
+pub struct Notch<T = f32> {
+    b0: T,
+    b1: T,
+    a1: T,
+    a2: T,
+    /// Transposed direct form II state
+    z: [T; 2],
+}
+
+impl<T: Float> Notch<T> {
+    /// Create a new notch at `frequency` Hz with quality factor `q`
+    pub fn new(frequency: T, q: T, sample_time: T) -> Self {
+        let (sin, cos) = design_sin_cos(T::PI2 * frequency * sample_time);
+        let alpha = sin / (T::from_f32(2.0) * q);
+        let a0 = T::ONE + alpha;
+        Self {
+            // b2 = b0, the numerator is symmetric
+            b0: T::ONE / a0,
+            b1: T::from_f32(-2.0) * cos / a0,
+            a1: T::from_f32(-2.0) * cos / a0,
+            a2: (T::ONE - alpha) / a0,
+            z: [T::ZERO; 2],
+        }
+    }
+
+    /// Filter a new sample
+    pub fn update(&mut self, x: T) -> T {
+        let y = self.b0 * x + self.z[0];
+        self.z[0] = self.b1 * x - self.a1 * y + self.z[1];
+        self.z[1] = self.b0 * x - self.a2 * y;
+        y
+    }
+
+    /// Filter state
+    pub(crate) fn state(&self) -> [T; 2] {
+        self.z
+    }
+
+    /// Restore a state returned by [`Notch::state`]
+    pub(crate) fn set_state(&mut self, state: [T; 2]) {
+        self.z = state;
+    }
+
+    /// Clear the filter state
+    pub fn reset(&mut self) {
+        self.z = [T::ZERO; 2];
+    }
+}
+
+/// sin and cos of `w0` in `0..=π` from their Taylor series
+///
+/// The notch depth hinges on `cos(w0)`: with the micromath or lut `sin_cos` a 150 Hz notch at
+/// 12 kHz passes 3.5% and 7.8% of the tone, the series is exact to f32 precision and costs a few
+/// multiplications once per design.
+fn design_sin_cos<T: Float>(w0: T) -> (T, T) {
+    let x2 = w0 * w0;
+    let (mut sin, mut cos) = (T::ONE, T::ONE);
+    // The x^21 and x^20 terms are below 1e-8 at π
+    for k in (1..=10).rev() {
+        let k = k as f32;
+        sin = T::ONE - x2 / T::from_f32(2.0 * k * (2.0 * k + 1.0)) * sin;
+        cos = T::ONE - x2 / T::from_f32(2.0 * k * (2.0 * k - 1.0)) * cos;
+    }
+    (w0 * sin, cos)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    /// Largest |output| over the second second of a unit tone at `hz`
+    fn residual(hz: f64) -> f32 {
+        let mut notch = Notch::new(150.0, 5.0, 1.0 / 12000.0);
+        let mut largest: f32 = 0.0;
+        for n in 0..24000 {
+            let phase = core::f64::consts::TAU * hz * n as f64 / 12000.0;
+            let output = notch.update(phase.cos() as f32);
+            if n >= 12000 {
+                largest = largest.max(output.abs());
+            }
+        }
+        largest
+    }
+
+    #[test]
+    fn removes_the_notch_frequency() {
+        assert!(residual(150.0) < 1e-3);
+        assert!(residual(50.0) > 0.99);
+    }
+
+    #[test]
+    fn design_series_matches_sin_cos() {
+        for step in 0..=100 {
+            let w0 = core::f64::consts::PI * step as f64 / 100.0;
+            let (sin, cos) = design_sin_cos(w0 as f32);
+            assert!((sin as f64 - w0.sin()).abs() < 1e-6);
+            assert!((cos as f64 - w0.cos()).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! Callbacks for PLL state transitions
+
+/// Receives the lock and grid state transitions of a [`SogiPll`](crate::SogiPll)
+///
+/// Every method defaults to doing nothing, so only the events of interest need implementing.
+/// The methods are called from inside `update`, keep them short.
+pub trait PllObserver {
+    /// [`SogiPll::is_locked`](crate::SogiPll::is_locked) became true
+    fn on_lock(&mut self) {}
+
+    /// [`SogiPll::is_locked`](crate::SogiPll::is_locked) became false
+    fn on_unlock(&mut self) {}
+
+    /// [`SogiPll::grid_present`](crate::SogiPll::grid_present) became false
+    fn on_grid_loss(&mut self) {}
+}
+
+/// Observer that ignores every event, the default for [`SogiPll`](crate::SogiPll)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoObserver;
+
+impl PllObserver for NoObserver {}
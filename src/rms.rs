@@ -0,0 +1,104 @@
+//! RMS averaged over one fundamental period
+
+use crate::float::accurate_sqrt;
+use crate::{Float, PllConfig};
+
+/// True RMS over the last fundamental period, from a circular buffer of squared samples
+///
+/// The period is `2π / omega_zero` rounded to whole samples and must fit in `N`, e.g.
+/// `N = 240` covers 50Hz at 12kHz and 60Hz up to 14.4kHz. Unlike [`PllResult::v_rms`](crate::PllResult::v_rms)
+/// this includes harmonics and does not ripple under distortion, but it lags by up to a period.
+#[derive(Clone, Debug)]
+pub struct CycleRms<const N: usize, T = f32> {
+    squares: [T; N],
+    /// Samples per period, at most N
+    len: usize,
+    index: usize,
+    /// Samples in the buffer, less than `len` during the first period
+    count: usize,
+    sum: T,
+}
+
+impl<const N: usize, T: Float> CycleRms<N, T> {
+    /// Create a new cycle RMS for the nominal frequency and sample time of a configuration
+    ///
+    /// Panics if a period is shorter than one sample or longer than `N` samples
+    pub fn new(config: &PllConfig<T>) -> Self {
+        let period = T::PI2 / (config.omega_zero * config.sample_time);
+        let len = (period.to_f32() + 0.5) as usize;
+        assert!(len >= 1 && len <= N, "one period must be 1 to N samples");
+
+        Self {
+            squares: [T::ZERO; N],
+            len,
+            index: 0,
+            count: 0,
+            sum: T::ZERO,
+        }
+    }
+
+    /// Add a new sample and return the RMS over the last period
+    pub fn update(&mut self, v: T) -> T {
+        let square = v * v;
+        self.sum += square - self.squares[self.index];
+        self.squares[self.index] = square;
+
+        self.index += 1;
+        if self.index == self.len {
+            self.index = 0;
+            // Drop the rounding error accumulated by the running sum once per period
+            let mut sum = T::ZERO;
+            for &square in &self.squares[..self.len] {
+                sum += square;
+            }
+            self.sum = sum;
+        }
+        if self.count < self.len {
+            self.count += 1;
+        }
+
+        self.rms()
+    }
+
+    /// RMS over the last period, or over the samples so far during the first period
+    pub fn rms(&self) -> T {
+        if self.count == 0 {
+            return T::ZERO;
+        }
+        accurate_sqrt(self.sum / T::from_f32(self.count as f32))
+    }
+
+    /// Number of samples in one period
+    pub fn samples_per_cycle(&self) -> usize {
+        self.len
+    }
+
+    /// Clear the buffer
+    pub fn reset(&mut self) {
+        self.squares = [T::ZERO; N];
+        self.index = 0;
+        self.count = 0;
+        self.sum = T::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn rms_of_a_unit_cosine() {
+        let config = PllConfig::default();
+        let mut rms = CycleRms::<240>::new(&config);
+        assert_eq!(rms.samples_per_cycle(), 240);
+        for n in 0..2400 {
+            let phase = core::f64::consts::TAU * 50.0 * n as f64 / 12000.0;
+            let value = rms.update(phase.cos() as f32);
+            if n >= 240 {
+                assert!((value - core::f32::consts::FRAC_1_SQRT_2).abs() < 1e-4);
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Synchronous reference frame PLL without the SOGI prefilter
+
+use crate::{Float, PllConfig, PllResult, QuadratureDelay, SogiPll};
+
+/// Classic SRF-PLL for comparison with [`SogiPll`]
+///
+/// The quadrature signal comes from a [`QuadratureDelay`] of `N` samples, a 90° shift when
+/// `N = sample_rate / (4 · nominal frequency)`, e.g. 60 at 12kHz and 50Hz. The phase detector
+/// and PI loop are the same as in [`SogiPll`], but there is no filtering, so harmonics and
+/// off-nominal frequencies show up directly as ripple in the estimate.
+#[derive(Clone, Debug)]
+pub struct SrfPll<const N: usize, T = f32> {
+    pll: SogiPll<T>,
+    delay: QuadratureDelay<N, T>,
+}
+
+impl<const N: usize, T: Float> SrfPll<N, T> {
+    /// Create a new SRF-PLL with a given configuration, `sogi_k` and `integrator` are unused
+    ///
+    /// Panics if the configuration is invalid, see [`SogiPll::try_new`]
+    pub fn new(config: PllConfig<T>) -> SrfPll<N, T> {
+        SrfPll {
+            pll: SogiPll::new(config),
+            delay: QuadratureDelay::new(),
+        }
+    }
+
+    /// Update the PLL with a new voltage measurement
+    ///
+    /// Panics if `N` is 0
+    pub fn update(&mut self, v: T) -> PllResult<T> {
+        let dt = self.pll.config.sample_time;
+        let omega = self.pll.next_omega(dt);
+
+        let v = v * self.pll.config.input_scale;
+        let (v, v_beta) = self.delay.update(v);
+        self.pll.track(v, v_beta, omega, dt)
+    }
+
+    /// Reset the PLL to its initial state, keeping the configuration
+    pub fn reset(&mut self) {
+        self.pll.reset();
+        self.delay.reset();
+    }
+
+    /// See [`SogiPll::is_locked`]
+    pub fn is_locked(&self) -> bool {
+        self.pll.is_locked()
+    }
+}
@@ -0,0 +1,164 @@
+//! Instantaneous symmetrical components of a three-phase system
+
+use crate::float::accurate_sqrt;
+use crate::{Float, Sogi};
+
+/// Magnitude and phase of one sequence component
+#[derive(Clone, Debug)]
+pub struct SequenceComponent<T = f32> {
+    /// Peak amplitude
+    pub magnitude: T,
+    /// Instantaneous angle of the phase a component in radians, -π..=π
+    pub phase: T,
+}
+
+/// Positive, negative and zero sequence components
+#[derive(Clone, Debug)]
+pub struct SymmetricalComponents<T = f32> {
+    pub positive: SequenceComponent<T>,
+    pub negative: SequenceComponent<T>,
+    pub zero: SequenceComponent<T>,
+}
+
+/// Symmetrical component estimator, one SOGI per phase
+///
+/// Each SOGI turns its phase into a rotating phasor `v_alpha + j·v_beta`, which are combined
+/// with the Fortescue transform using `a = e^(j2π/3)`:
+///
+/// ```text
+/// V+ = (Va + a·Vb + a²·Vc) / 3
+/// V- = (Va + a²·Vb + a·Vc) / 3
+/// V0 = (Va + Vb + Vc) / 3
+/// ```
+#[derive(Clone, Debug)]
+pub struct SymmetricalSogi<T = f32> {
+    sogi_a: Sogi<T>,
+    sogi_b: Sogi<T>,
+    sogi_c: Sogi<T>,
+}
+
+impl<T: Float> SymmetricalSogi<T> {
+    /// Create a new estimator with a given k and sample time(used for the integrators)
+    pub fn new(k: T, sample_time: T) -> Self {
+        Self {
+            sogi_a: Sogi::new(k, sample_time),
+            sogi_b: Sogi::new(k, sample_time),
+            sogi_c: Sogi::new(k, sample_time),
+        }
+    }
+
+    /// Update the estimator with a new three-phase voltage measurement and omega, e.g. from
+    /// a [`SogiPll`](crate::SogiPll) locked onto the same system
+    pub fn update_three_phase(
+        &mut self,
+        va: T,
+        vb: T,
+        vc: T,
+        omega: T,
+    ) -> SymmetricalComponents<T> {
+        let a = self.sogi_a.update(va, omega);
+        let b = self.sogi_b.update(vb, omega);
+        let c = self.sogi_c.update(vc, omega);
+
+        let (b_a, b_a2) = (rotate_a(b), rotate_a2(b));
+        let (c_a, c_a2) = (rotate_a(c), rotate_a2(c));
+
+        let third = T::ONE / T::from_f32(3.0);
+        let positive = (
+            (a.0 + b_a.0 + c_a2.0) * third,
+            (a.1 + b_a.1 + c_a2.1) * third,
+        );
+        let negative = (
+            (a.0 + b_a2.0 + c_a.0) * third,
+            (a.1 + b_a2.1 + c_a.1) * third,
+        );
+        let zero = ((a.0 + b.0 + c.0) * third, (a.1 + b.1 + c.1) * third);
+
+        SymmetricalComponents {
+            positive: component(positive),
+            negative: component(negative),
+            zero: component(zero),
+        }
+    }
+
+    /// Clear the state of all three SOGIs
+    pub fn reset(&mut self) {
+        self.sogi_a.reset();
+        self.sogi_b.reset();
+        self.sogi_c.reset();
+    }
+}
+
+/// Multiply a phasor by `a = e^(j2π/3)`
+fn rotate_a<T: Float>((re, im): (T, T)) -> (T, T) {
+    let half = T::from_f32(0.5);
+    let sin = T::from_f32(0.866_025_4);
+    (-re * half - im * sin, re * sin - im * half)
+}
+
+/// Multiply a phasor by `a² = e^(-j2π/3)`
+fn rotate_a2<T: Float>((re, im): (T, T)) -> (T, T) {
+    let half = T::from_f32(0.5);
+    let sin = T::from_f32(0.866_025_4);
+    (-re * half + im * sin, -re * sin - im * half)
+}
+
+fn component<T: Float>((re, im): (T, T)) -> SequenceComponent<T> {
+    SequenceComponent {
+        magnitude: accurate_sqrt(re * re + im * im),
+        phase: im.atan2(re),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::f64::consts::TAU;
+
+    const SAMPLE_RATE: f64 = 12000.0;
+
+    /// Sequence magnitudes after a second of 50Hz with the given positive, negative and zero
+    /// sequence amplitudes
+    fn magnitudes(positive: f64, negative: f64, zero: f64) -> (f32, f32, f32) {
+        let omega = (TAU * 50.0) as f32;
+        let mut sogi = SymmetricalSogi::new(1.0, (1.0 / SAMPLE_RATE) as f32);
+        let voltage = |positive_shift: f64, negative_shift: f64, phase: f64| {
+            positive * (phase - positive_shift).cos()
+                + negative * (phase - negative_shift).cos()
+                + zero * phase.cos()
+        };
+        let third = TAU / 3.0;
+        let mut result = None;
+        for n in 0..12000 {
+            let p = TAU * 50.0 * n as f64 / SAMPLE_RATE;
+            let va = voltage(0.0, 0.0, p) as f32;
+            let vb = voltage(third, -third, p) as f32;
+            let vc = voltage(-third, third, p) as f32;
+            result = Some(sogi.update_three_phase(va, vb, vc, omega));
+        }
+        let result = result.unwrap();
+        (
+            result.positive.magnitude,
+            result.negative.magnitude,
+            result.zero.magnitude,
+        )
+    }
+
+    #[test]
+    fn balanced_magnitude() {
+        let (positive, negative, zero) = magnitudes(1.5, 0.0, 0.0);
+        assert!((positive - 1.5).abs() < 1e-3);
+        assert!(negative < 1e-3);
+        assert!(zero < 1e-3);
+    }
+
+    #[test]
+    fn unbalanced_magnitudes() {
+        let (positive, negative, zero) = magnitudes(1.5, 0.3, 0.2);
+        assert!((positive - 1.5).abs() < 1e-3);
+        assert!((negative - 0.3).abs() < 1e-3);
+        assert!((zero - 0.2).abs() < 1e-3);
+    }
+}
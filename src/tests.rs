@@ -0,0 +1,1055 @@
+extern crate std;
+
+use crate::*;
+use core::f32::consts::PI;
+
+const SAMPLE_RATE: f32 = 12000.0;
+
+/// `amplitude · cos(2π · hz · n / SAMPLE_RATE + phase)`, computed in `f64`
+fn cosine(amplitude: f32, hz: f32, phase: f32, n: u32) -> f32 {
+    let t = n as f64 / SAMPLE_RATE as f64;
+    (amplitude as f64 * (core::f64::consts::TAU * hz as f64 * t + phase as f64).cos()) as f32
+}
+
+/// `angle - truth` wrapped to -π..π
+fn wrap_error(angle: f32, truth: f64) -> f32 {
+    use core::f64::consts::{PI, TAU};
+    let error = (angle as f64 - truth).rem_euclid(TAU);
+    (if error > PI { error - TAU } else { error }) as f32
+}
+
+/// Error of `theta` against the phase of `cosine(_, hz, phase, n)`
+///
+/// theta is taken after the step to the next sample, so a locked PLL leads by `omega · dt`.
+fn theta_error(theta: f32, hz: f32, phase: f32, n: u32) -> f32 {
+    let t = n as f64 / SAMPLE_RATE as f64;
+    wrap_error(theta, core::f64::consts::TAU * hz as f64 * t + phase as f64)
+}
+
+#[test]
+fn theta_degrees_half_turn() {
+    let mut result = SogiPll::new(PllConfig::default()).update(0.0);
+    result.theta = PI;
+    assert!((result.theta_degrees() - 180.0).abs() < 1e-4);
+}
+
+#[test]
+fn negative_integral_limit_rejected() {
+    for limit in [-1.0, f32::NAN] {
+        let config = PllConfig {
+            pi_integral_limit: limit,
+            ..Default::default()
+        };
+        assert_eq!(SogiPll::try_new(config).err(), Some(PllError::InvalidLimit));
+    }
+}
+
+#[test]
+fn negative_integrator_limit_rejected() {
+    for limit in [-1.0, f32::NAN] {
+        let config = PllConfig {
+            integrator_limit: limit,
+            ..Default::default()
+        };
+        assert_eq!(SogiPll::try_new(config).err(), Some(PllError::InvalidLimit));
+    }
+}
+
+#[test]
+fn integral_limit_bounds_off_frequency_windup() {
+    // Locked to 60Hz with a 50Hz omega_zero the PI output stays at about 2π·10 rad/s, which
+    // the integrator keeps accumulating
+    let run = |limit: f32| {
+        let mut pll = SogiPll::new(PllConfig {
+            pi_integral_limit: limit,
+            ..Default::default()
+        });
+        let mut largest: f32 = 0.0;
+        for n in 0..24000 {
+            pll.update(cosine(1.0, 60.0, 0.0, n));
+            largest = largest.max(pll.integrator_state().abs());
+        }
+        largest
+    };
+    assert!(run(f32::INFINITY) > 5.0);
+    assert!(run(1.0) <= 1.0);
+}
+
+#[test]
+fn inverted_omega_range_rejected() {
+    for (min, max) in [(400.0, 300.0), (f32::NAN, 400.0), (0.0, f32::NAN)] {
+        let config = PllConfig {
+            omega_min: min,
+            omega_max: max,
+            ..Default::default()
+        };
+        assert_eq!(
+            SogiPll::try_new(config).err(),
+            Some(PllError::InvalidOmegaRange)
+        );
+    }
+}
+
+#[test]
+fn omega_max_pins_a_far_input() {
+    // At this amplitude the loop gain is high enough to pull towards 200Hz
+    let omega_max = 2.0 * PI * 100.0;
+    let mut pll = SogiPll::new(PllConfig {
+        omega_max,
+        ..Default::default()
+    });
+    let mut pinned = 0;
+    for n in 0..24000 {
+        let result = pll.update(cosine(10.0, 200.0, 0.0, n));
+        assert!(result.omega <= omega_max);
+        if result.omega == omega_max {
+            pinned += 1;
+        }
+    }
+    assert!(pinned > 1000);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use std::format;
+
+    let config = PllConfig {
+        omega_max: 2.0 * PI * 55.0,
+        phase_detector: PhaseDetector::Atan2,
+        ..Default::default()
+    };
+    let mut buffer = [0u8; 512];
+    let bytes = postcard::to_slice(&config, &mut buffer).unwrap();
+    let decoded: PllConfig = postcard::from_bytes(bytes).unwrap();
+    assert_eq!(format!("{decoded:?}"), format!("{config:?}"));
+
+    let mut pll = SogiPll::new(config);
+    let mut result = pll.update(0.0);
+    for n in 1..1000 {
+        result = pll.update(cosine(1.0, 50.0, 0.0, n));
+    }
+    let bytes = postcard::to_slice(&result, &mut buffer).unwrap();
+    let decoded: PllResult = postcard::from_bytes(bytes).unwrap();
+    assert_eq!(format!("{decoded:?}"), format!("{result:?}"));
+}
+
+#[test]
+fn dc_rejection_removes_the_offset_ripple() {
+    // Largest |theta error| over each second of 50Hz with a 0.2 DC offset
+    let run = |tau: f32| {
+        let mut pll = SogiPll::new(PllConfig {
+            dc_reject_tau: tau,
+            ..Default::default()
+        });
+        let mut errors = [0.0f32; 5];
+        for n in 0..60000 {
+            let result = pll.update(0.2 + cosine(1.0, 50.0, 0.0, n));
+            let second = (n / 12000) as usize;
+            errors[second] = errors[second].max(theta_error(result.theta, 50.0, 0.0, n).abs());
+        }
+        errors
+    };
+    assert!(run(0.0)[4] > 0.15);
+    let errors = run(0.5);
+    assert!(errors[4] < errors[1]);
+    assert!(errors[4] < 0.04);
+}
+
+#[test]
+fn nominal_frequency_change_reconverges() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    let mut phase = 0.0f64;
+    let mut result = pll.update(1.0);
+    for n in 1..36000 {
+        let hz = if n < 12000 { 50.0 } else { 60.0 };
+        if n == 12000 {
+            assert!((result.frequency_hz() - 50.0).abs() < 0.05);
+            pll.set_nominal_frequency(60.0);
+        }
+        phase += core::f64::consts::TAU * hz / SAMPLE_RATE as f64;
+        result = pll.update(phase.cos() as f32);
+    }
+    assert!((result.frequency_hz() - 60.0).abs() < 0.05);
+    assert!(pll.is_locked());
+}
+
+#[test]
+fn trapezoidal_has_less_phase_lag_at_1khz() {
+    // Mean and largest |theta error| over the third second at a 1kHz sample rate
+    let run = |integrator: IntegratorKind| {
+        let sample_rate = 1000.0f64;
+        let mut pll = SogiPll::new(PllConfig {
+            sample_time: (1.0 / sample_rate) as f32,
+            integrator,
+            ..Default::default()
+        });
+        let (mut mean, mut largest) = (0.0f64, 0.0f32);
+        for n in 0..3000 {
+            let phase = core::f64::consts::TAU * 50.0 * n as f64 / sample_rate;
+            let result = pll.update(phase.cos() as f32);
+            if n >= 2000 {
+                // Undo the step to the next sample
+                let error = wrap_error(result.theta - result.phase_step, phase);
+                mean += error as f64 / 1000.0;
+                largest = largest.max(error.abs());
+            }
+        }
+        (mean, largest)
+    };
+    let (mean, largest) = run(IntegratorKind::Multistep);
+    assert!(mean > 0.01 && largest < 0.015);
+    let (mean, largest) = run(IntegratorKind::Trapezoidal);
+    assert!(mean.abs() < 0.0005 && largest < 0.0005);
+}
+
+#[test]
+fn v_peak_and_v_rms_match_the_amplitude() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    for n in 0..12000 {
+        // The micromath sqrt is exact at 2.0 but reads 1.5625 for 1.5
+        let result = pll.update(cosine(1.5, 50.0, 0.0, n));
+        if n > 6000 {
+            assert!((result.v_peak() - 1.5).abs() < 1e-3);
+            assert!((result.v_rms() - 1.5 * core::f32::consts::FRAC_1_SQRT_2).abs() < 1e-3);
+        }
+    }
+}
+
+#[test]
+fn slew_limit_bounds_the_omega_step() {
+    let omega_slew_max = 2.0 * PI * 20.0;
+    let max_step = omega_slew_max / SAMPLE_RATE;
+    let mut pll = SogiPll::new(PllConfig {
+        omega_slew_max,
+        ..Default::default()
+    });
+    let mut omega_prev = pll.update(1.0).omega;
+    let mut limited = 0;
+    for n in 1..24000 {
+        // 90° phase step after half a second
+        let phase = if n < 6000 { 0.0 } else { PI / 2.0 };
+        let omega = pll.update(cosine(1.0, 50.0, phase, n)).omega;
+        let step = (omega - omega_prev).abs();
+        // Allow for the rounding of omega itself
+        assert!(step <= max_step + 1e-4);
+        if step > max_step - 1e-4 {
+            limited += 1;
+        }
+        omega_prev = omega;
+    }
+    assert!(limited > 0);
+}
+
+#[test]
+fn rocof_follows_a_frequency_ramp() {
+    // 50Hz rising at 2Hz/s after the first second
+    let slope = 2.0;
+    let mut pll = SogiPll::new(PllConfig::default());
+    assert_eq!(pll.update(1.0).rocof_hz_per_s, 0.0);
+    let mut mean = 0.0f64;
+    for n in 1..48000 {
+        let t = n as f64 / SAMPLE_RATE as f64;
+        let ramp = (t - 1.0).max(0.0);
+        let phase = core::f64::consts::TAU * (50.0 * t + slope / 2.0 * ramp * ramp);
+        let result = pll.update(phase.cos() as f32);
+        if n >= 36000 {
+            mean += result.rocof_hz_per_s as f64 / 12000.0;
+        }
+    }
+    assert!((mean - slope).abs() < 0.05);
+}
+
+#[test]
+fn standard_sogi_matches_the_original_expression() {
+    let (k, dt, omega) = (1.0f32, 1.0 / SAMPLE_RATE, 2.0 * PI * 50.0);
+    let mut sogi = Sogi::new(k, dt);
+    // Third order integrators and feedback as first written
+    let (mut z1, mut z2) = ([0.0f32; 3], [0.0f32; 3]);
+    let value = |z: &[f32; 3]| z[0] * 23.0 - z[1] * 16.0 + z[2] * 5.0;
+    let step = |z: &mut [f32; 3], x: f32| *z = [z[0] + x * (dt / 12.0), z[0], z[1]];
+    for n in 0..2400 {
+        let v = cosine(1.0, 50.0, 0.0, n);
+        let (v_alpha, v_beta) = (value(&z1), value(&z2));
+        step(&mut z1, ((v - v_alpha) * k - v_beta) * omega);
+        step(&mut z2, v_alpha * omega);
+        assert_eq!(sogi.update(v, omega), (v_alpha, v_beta));
+    }
+}
+
+#[test]
+fn normalized_sogi_settles_at_off_nominal_frequencies() {
+    // Last sample the frequency estimate was more than 0.05Hz off
+    let settling = |hz: f32, sogi_normalized: bool| {
+        let mut pll = SogiPll::new(PllConfig {
+            sogi_normalized,
+            ..Default::default()
+        });
+        let mut last_off = 0;
+        for n in 0..24000 {
+            let result = pll.update(cosine(1.0, hz, 0.0, n));
+            if (result.frequency_hz() - hz).abs() > 0.05 {
+                last_off = n;
+            }
+        }
+        last_off
+    };
+    // Below nominal the normalized SOGI keeps the wider nominal bandwidth and settles
+    // faster, above nominal the standard SOGI is the wider one
+    let (standard, normalized) = (settling(40.0, false), settling(40.0, true));
+    assert!(normalized < standard && standard < 1200);
+    let (standard, normalized) = (settling(60.0, false), settling(60.0, true));
+    assert!(standard < normalized && normalized < 1200);
+}
+
+/// `f32` newtype using one backend for the transcendental functions regardless of the
+/// enabled features
+macro_rules! backend {
+    ($name:ident, $sin_cos:expr, $sqrt:expr, $atan2:expr) => {
+        #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+        struct $name(f32);
+
+        impl core::ops::Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl core::ops::Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl core::ops::Mul for $name {
+            type Output = Self;
+            fn mul(self, rhs: Self) -> Self {
+                Self(self.0 * rhs.0)
+            }
+        }
+
+        impl core::ops::Div for $name {
+            type Output = Self;
+            fn div(self, rhs: Self) -> Self {
+                Self(self.0 / rhs.0)
+            }
+        }
+
+        impl core::ops::Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl core::ops::AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl Float for $name {
+            const ZERO: Self = Self(0.0);
+            const ONE: Self = Self(1.0);
+            const PI: Self = Self(PI);
+            const PI2: Self = Self(PI * 2.0);
+            const FRAC_1_2PI: Self = Self(1.0 / (PI * 2.0));
+            const FRAC_1_SQRT_2: Self = Self(core::f32::consts::FRAC_1_SQRT_2);
+            const INFINITY: Self = Self(f32::INFINITY);
+
+            fn from_f32(value: f32) -> Self {
+                Self(value)
+            }
+
+            fn to_f32(self) -> f32 {
+                self.0
+            }
+
+            fn sin_cos(self) -> (Self, Self) {
+                let (sin, cos) = ($sin_cos)(self.0);
+                (Self(sin), Self(cos))
+            }
+
+            fn sqrt(self) -> Self {
+                Self(($sqrt)(self.0))
+            }
+
+            fn atan2(self, x: Self) -> Self {
+                Self(($atan2)(self.0, x.0))
+            }
+
+            fn rem(self, rhs: Self) -> Self {
+                Self(self.0 % rhs.0)
+            }
+
+            fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+
+            fn is_finite(self) -> bool {
+                self.0.is_finite()
+            }
+
+            fn clamp(self, min: Self, max: Self) -> Self {
+                Self(self.0.clamp(min.0, max.0))
+            }
+        }
+    };
+}
+
+backend!(
+    Micromath,
+    micromath::F32Ext::sin_cos,
+    micromath::F32Ext::sqrt,
+    micromath::F32Ext::atan2
+);
+backend!(
+    Libm,
+    |x| (libm::sinf(x), libm::cosf(x)),
+    libm::sqrtf,
+    libm::atan2f
+);
+
+/// Mean and largest |theta error| over the second second of a locked 50Hz input
+fn backend_phase_error<T: Float>() -> (f32, f32) {
+    let mut pll = SogiPll::<T>::new(PllConfig::default());
+    let (mut mean, mut largest) = (0.0f32, 0.0f32);
+    for n in 0..24000 {
+        let result = pll.update(T::from_f32(cosine(1.0, 50.0, 0.0, n)));
+        if n >= 12000 {
+            let theta = (result.theta - result.phase_step).to_f32();
+            let error = theta_error(theta, 50.0, 0.0, n);
+            mean += error / 12000.0;
+            largest = largest.max(error.abs());
+        }
+    }
+    (mean, largest)
+}
+
+#[test]
+fn libm_backend_removes_the_phase_bias() {
+    // Both leave a mean error of a few µrad, micromath adds a ripple of about 0.16 mrad
+    let (mean, largest) = backend_phase_error::<Micromath>();
+    assert!(mean.abs() < 1e-5 && largest > 1e-4 && largest < 3e-4);
+    let (mean, largest) = backend_phase_error::<Libm>();
+    assert!(mean.abs() < 1e-5 && largest < 2e-5);
+}
+
+#[cfg(feature = "f64")]
+#[test]
+fn f64_counts_stay_exact_past_f32_precision() {
+    let count = (1u64 << 25) + 1;
+    let config = PllConfig::<f64>::default();
+    let sample_time = config.sample_time;
+    let mut pll = SogiPll64::new(config);
+    let mut result = pll.update(1.0);
+    result.revolutions = count as i32;
+    result.theta = 0.0;
+    assert_eq!(
+        result.theta_unwrapped(),
+        count as f64 * core::f64::consts::TAU
+    );
+
+    let mut state = pll.snapshot();
+    state.sample_count = count;
+    pll.restore(&state);
+    assert_eq!(pll.elapsed_seconds(), count as f64 * sample_time);
+}
+
+#[test]
+fn sample_rate_change_keeps_lock() {
+    // 12kHz for a second, then 8kHz for a second. The multistep taps still hold the old
+    // step on the first sample after the change, giving a one sample q of about 0.012
+    let mut pll = SogiPll::new(PllConfig {
+        unlock_threshold: 0.05,
+        ..Default::default()
+    });
+    let mut phase = 0.0f64;
+    let mut result = pll.update(1.0);
+    for n in 1..20000 {
+        let sample_rate = if n < 12000 { 12000.0 } else { 8000.0 };
+        if n == 12000 {
+            assert!(pll.is_locked());
+            pll.set_sample_time(1.0 / sample_rate as f32);
+        }
+        phase += core::f64::consts::TAU * 50.0 / sample_rate;
+        result = pll.update(phase.cos() as f32);
+        if n >= 12000 {
+            assert!(pll.is_locked());
+            assert!((result.frequency_hz() - 50.0).abs() < 0.5);
+        }
+    }
+    assert!(pll.is_locked());
+    assert!((result.frequency_hz() - 50.0).abs() < 0.02);
+}
+
+#[test]
+fn frequency_filter_reduces_noise_variance() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Variance of the reported frequency over the second second with 5% uniform noise
+    let variance = |freq_filter_tau: f32| {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut pll = SogiPll::new(PllConfig {
+            freq_filter_tau,
+            ..Default::default()
+        });
+        let (mut sum, mut sum_sq) = (0.0f64, 0.0f64);
+        for n in 0..24000 {
+            let noise = rng.gen_range(-0.05..0.05);
+            let hz = pll.update(cosine(1.0, 50.0, 0.0, n) + noise).frequency_hz() as f64;
+            if n >= 12000 {
+                sum += hz;
+                sum_sq += hz * hz;
+            }
+        }
+        let mean = sum / 12000.0;
+        sum_sq / 12000.0 - mean * mean
+    };
+    assert!(variance(0.05) < variance(0.0) / 10.0);
+}
+
+#[test]
+fn derivative_term_reduces_ramp_lag() {
+    // Mean q over the last second of a 5Hz/s ramp starting after the first second. The phase
+    // lag keeps growing along the ramp, so the derivative of q pushes omega ahead of it
+    let lag = |pi_derivative_gain: f32| {
+        let mut pll = SogiPll::new(PllConfig {
+            pi_derivative_gain,
+            pi_derivative_tau: 0.005,
+            ..Default::default()
+        });
+        let mut mean = 0.0f64;
+        for n in 0..36000 {
+            let t = n as f64 / SAMPLE_RATE as f64;
+            let ramp = (t - 1.0).max(0.0);
+            let phase = core::f64::consts::TAU * (50.0 * t + 2.5 * ramp * ramp);
+            let result = pll.update(phase.cos() as f32);
+            if n >= 24000 {
+                mean += result.q as f64 / 12000.0;
+            }
+        }
+        mean
+    };
+    let (pi, pid) = (lag(0.0), lag(10.0));
+    assert!(pi > 0.25);
+    assert!(pid < 0.98 * pi);
+}
+
+#[test]
+fn soft_start_holds_omega_near_omega_zero() {
+    // Largest frequency deviation from 50Hz over the first 20 ms and the first 100 ms of a
+    // 55Hz input out of phase with the initial theta
+    let deviation = |soft_start_samples: u32| {
+        let mut pll = SogiPll::new(PllConfig {
+            soft_start_samples,
+            ..Default::default()
+        });
+        let mut largest = [0.0f32; 2];
+        for n in 0..1200 {
+            let result = pll.update(cosine(1.0, 55.0, 1.5, n));
+            if n == 0 {
+                assert_eq!(result.omega, 2.0 * PI * 50.0);
+            }
+            let slot = &mut largest[usize::from(n >= 240)];
+            *slot = slot.max((result.frequency_hz() - 50.0).abs());
+        }
+        (largest[0], largest[0].max(largest[1]))
+    };
+    let (plain_start, plain_ramp) = deviation(0);
+    let (soft_start, soft_ramp) = deviation(1200);
+    assert!(soft_start < plain_start / 4.0);
+    assert!(soft_ramp < plain_ramp * 0.6);
+}
+
+#[test]
+fn zero_crossing_marks_the_rising_edge() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    let mut v_prev = 0.0;
+    let mut crossings = 0;
+    for n in 0..12000 {
+        let v = cosine(1.0, 50.0, 0.0, n);
+        if pll.update(v).zero_crossing && n >= 6000 {
+            // theta leads by one sample, so the flag can come a sample early
+            assert!(v.abs() < 0.06 && v > v_prev);
+            crossings += 1;
+        }
+        v_prev = v;
+    }
+    assert_eq!(crossings, 25);
+}
+
+#[test]
+fn in_phase_current_has_no_reactive_power() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    let mut current_sogi = Sogi::new(1.0, 1.0 / SAMPLE_RATE);
+    for n in 0..12000 {
+        let result = pll.update(cosine(1.0, 50.0, 0.0, n));
+        let (i_alpha, i_beta) = current_sogi.update(cosine(0.5, 50.0, 0.0, n), result.omega);
+        if n >= 6000 {
+            let (p, q) = result.power(i_alpha, i_beta);
+            assert!((p - 0.5).abs() < 0.005);
+            assert!(q.abs() < 0.005);
+        }
+    }
+}
+
+#[test]
+fn theta_unwrapped_is_continuous_with_a_phase_offset() {
+    let mut pll = SogiPll::new(PllConfig {
+        phase_offset: 0.5,
+        ..Default::default()
+    });
+    let mut previous = pll.update(1.0).theta_unwrapped();
+    for n in 1..12000 {
+        let unwrapped = pll.update(cosine(1.0, 50.0, 0.0, n)).theta_unwrapped();
+        // One step is about 0.026 rad, a missed or extra turn shows up as 2π
+        assert!((unwrapped - previous).abs() < 0.5);
+        previous = unwrapped;
+    }
+}
+
+#[test]
+fn atan2_detector_pulls_in_faster_after_90_degrees() {
+    // Samples after a 90° phase step until theta stays within 0.02 rad of the input
+    let pull_in = |phase_detector: PhaseDetector| {
+        let mut pll = SogiPll::new(PllConfig {
+            phase_detector,
+            ..Default::default()
+        });
+        let mut last_off = 0;
+        for n in 0..12000 {
+            let phase = if n < 6000 { 0.0 } else { PI / 2.0 };
+            let result = pll.update(cosine(1.0, 50.0, phase, n));
+            assert!(result.theta.is_finite());
+            let error = theta_error(result.theta - result.phase_step, 50.0, phase, n);
+            if error.abs() > 0.02 {
+                last_off = n;
+            }
+        }
+        last_off - 6000
+    };
+    let (quadrature, atan2) = (
+        pull_in(PhaseDetector::Quadrature),
+        pull_in(PhaseDetector::Atan2),
+    );
+    assert!(atan2 < quadrature);
+}
+
+#[test]
+fn phase_wrap_matches_the_remainder() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    let mut theta = pll.update(1.0).theta;
+    let mut wraps = 0;
+    for n in 1..12000 {
+        let hz = if n < 6000 { 50.0 } else { 55.0 };
+        let result = pll.update(cosine(1.0, hz, 0.0, n));
+        assert_eq!(result.theta, (theta + result.phase_step) % (2.0 * PI));
+        wraps += usize::from(result.theta < theta);
+        theta = result.theta;
+    }
+    assert!(wraps > 50);
+}
+
+#[test]
+fn sogi_matches_the_original_outputs() {
+    // (v_alpha, v_beta) bits from the original Sogi::new(1.0, 1.0 / 12000.0) at a fixed
+    // 50Hz omega
+    const GOLDEN: [(u32, (u32, u32)); 6] = [
+        (1, (0x3d4d87ac, 0x00000000)),
+        (10, (0x3e7033a8, 0x3d0bdf5d)),
+        (100, (0xbf13b418, 0x3e87181f)),
+        (1000, (0x3effff35, 0x3f5db372)),
+        (5000, (0x3effff30, 0xbf5db2de)),
+        (11999, (0x3f7fe8a9, 0xbcd66152)),
+    ];
+    let mut sogi = Sogi::new(1.0, 1.0 / SAMPLE_RATE);
+    let omega = 2.0 * PI * 50.0;
+    let mut golden = GOLDEN.iter().peekable();
+    for n in 0..12000 {
+        let (v_alpha, v_beta) = sogi.update(cosine(1.0, 50.0, 0.0, n), omega);
+        if let Some(&(_, bits)) = golden.next_if(|&&(at, _)| at == n) {
+            assert_eq!((v_alpha.to_bits(), v_beta.to_bits()), bits);
+        }
+    }
+    assert!(golden.next().is_none());
+}
+
+/// The original used micromath for sin/cos
+#[cfg(not(any(feature = "libm", feature = "lut")))]
+#[test]
+fn default_pll_matches_the_original_outputs() {
+    // (theta, omega) bits from the original SogiPll with the recommended parameters
+    const GOLDEN: [(u32, (u32, u32)); 6] = [
+        (1, (0x3d567750, 0x439d1463)),
+        (10, (0x3e92de6f, 0x439b42cd)),
+        (100, (0x402c36dc, 0x43bae6fb)),
+        (1000, (0x3faf9f03, 0x439d09bb)),
+        (5000, (0x40b1fe0e, 0x439d144e)),
+        (11999, (0x3e99961e, 0x439d2c96)),
+    ];
+    let mut pll = SogiPll::new(PllConfig::default());
+    let mut golden = GOLDEN.iter().peekable();
+    for n in 0..12000 {
+        let result = pll.update(cosine(1.0, 50.0, 0.3, n));
+        if let Some(&(_, bits)) = golden.next_if(|&&(at, _)| at == n) {
+            assert_eq!((result.theta.to_bits(), result.omega.to_bits()), bits);
+        }
+    }
+    assert!(golden.next().is_none());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn state_postcard_round_trip() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    for n in 0..3000 {
+        pll.update(cosine(1.0, 50.0, 0.0, n));
+    }
+    let mut buffer = [0u8; 256];
+    let bytes = postcard::to_slice(&pll.snapshot(), &mut buffer).unwrap();
+    let state: PllState = postcard::from_bytes(bytes).unwrap();
+    assert_eq!(state, pll.snapshot());
+
+    let mut resumed = SogiPll::new(PllConfig::default());
+    resumed.restore(&state);
+    for n in 3000..4000 {
+        let v = cosine(1.0, 50.0, 0.0, n);
+        assert_eq!(resumed.update(v).theta, pll.update(v).theta);
+    }
+
+    // Largest varints for every integer
+    let mut state = pll.snapshot();
+    state.revolutions = i32::MIN;
+    state.lock_count = u32::MAX;
+    state.grid_count = u32::MAX;
+    state.islanding_count = u32::MAX;
+    state.sample_count = u64::MAX;
+    state.first_lock = Some(u64::MAX);
+    assert_eq!(postcard::to_slice(&state, &mut buffer).unwrap().len(), 152);
+}
+
+#[test]
+fn frequency_from_phase_is_measured_from_alpha_beta() {
+    // With a slow frequency filter the reported omega lags a 50Hz to 52Hz step, while the
+    // estimate from the SOGI outputs follows within a few cycles
+    let mut pll = SogiPll::new(PllConfig {
+        freq_filter_tau: 0.5,
+        ..Default::default()
+    });
+    let mut phase = 0.0f64;
+    assert_eq!(pll.update(1.0).omega_from_phase, 0.0);
+    for n in 1..8400 {
+        let hz = if n < 6000 { 50.0 } else { 52.0 };
+        phase += core::f64::consts::TAU * hz / SAMPLE_RATE as f64;
+        let result = pll.update(phase.cos() as f32);
+        // Ripples by a few hundredths of a Hz as alpha/beta are not exactly circular
+        if (3000..6000).contains(&n) {
+            assert!((result.frequency_from_phase() - 50.0).abs() < 0.05);
+        }
+        if n >= 7200 {
+            assert!((result.frequency_from_phase() - 52.0).abs() < 0.1);
+            assert!(result.frequency_hz() < 51.0);
+        }
+    }
+}
+
+#[test]
+fn rotation_between_matches_the_exact_angle() {
+    for step in [0.001f32, 0.026, 0.3, 0.46, 1.0, 3.0, -0.026, -2.0] {
+        let (start_sin, start_cos) = 0.7f32.sin_cos();
+        let (end_sin, end_cos) = (0.7 + step).sin_cos();
+        let rotation = crate::rotation_between((start_cos, start_sin), (end_cos, end_sin));
+        // The series up to atan(0.5), the backend's atan2 beyond
+        let tolerance = if step.abs() < 0.46 {
+            5e-4 * step.abs() + 1e-6
+        } else {
+            5e-3
+        };
+        assert!((rotation - step).abs() < tolerance, "{step}");
+    }
+}
+
+#[test]
+fn v_rms_true_of_a_unit_cosine() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    for n in 0..12000 {
+        pll.update(cosine(1.0, 50.0, 0.0, n));
+        if n > 6000 {
+            // The micromath sqrt alone is off by 0.043 here
+            assert!((pll.v_rms_true() - core::f32::consts::FRAC_1_SQRT_2).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn unlock_hysteresis_stops_lock_chatter() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Lock flag changes over the last two seconds with 5% uniform noise, which keeps |q|
+    // wandering across the 0.01 lock threshold
+    let toggles = |unlock_threshold: f32| {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut pll = SogiPll::new(PllConfig {
+            unlock_threshold,
+            ..Default::default()
+        });
+        let mut toggles = 0;
+        let mut locked = false;
+        for n in 0..36000 {
+            pll.update(cosine(1.0, 50.0, 0.0, n) + rng.gen_range(-0.05..0.05));
+            if n >= 12000 && pll.is_locked() != locked {
+                toggles += 1;
+            }
+            locked = pll.is_locked();
+        }
+        (toggles, locked)
+    };
+    assert!(toggles(0.0).0 > 10);
+    assert_eq!(toggles(0.03), (0, true));
+}
+
+#[test]
+fn feedforward_ramp_leaves_no_residual_q() {
+    use core::f64::consts::TAU;
+
+    // Largest |q| during a 50 to 55 Hz ramp over one second
+    let worst_q = |feedforward: bool| {
+        let mut pll = SogiPll::new(PllConfig::default());
+        let mut worst: f32 = 0.0;
+        for n in 0..36000 {
+            let t = n as f64 / SAMPLE_RATE as f64;
+            let ramp = (t - 1.0).clamp(0.0, 1.0);
+            let phase = TAU * (50.0 * t + 2.5 * ramp * ramp + 5.0 * (t - 2.0).max(0.0));
+            if feedforward {
+                pll.set_feedforward_omega((TAU * 5.0 * ramp) as f32);
+            }
+            let q = pll.update(phase.cos() as f32).q;
+            if (12000..24000).contains(&n) {
+                worst = worst.max(q.abs());
+            }
+        }
+        worst
+    };
+    assert!(worst_q(false) > 0.1);
+    // What is left is the micromath ripple
+    assert!(worst_q(true) < 0.005);
+}
+
+#[test]
+fn islanding_trip_follows_the_filtered_rocof() {
+    use core::f64::consts::TAU;
+
+    // Whether the trip fires after the first second, while the input ramps at `ramp` Hz/s
+    // from there on
+    let trips = |ramp: f64, islanding_rocof_tau: f32| {
+        let mut pll = SogiPll::new(PllConfig {
+            islanding_rocof_max: 1.0,
+            islanding_rocof_tau,
+            ..Default::default()
+        });
+        let mut tripped = false;
+        for n in 0..36000 {
+            let t = n as f64 / SAMPLE_RATE as f64;
+            let ramped = (t - 1.0).max(0.0);
+            pll.update((TAU * (50.0 * t + 0.5 * ramp * ramped * ramped)).cos() as f32);
+            tripped |= n >= 12000 && pll.islanding_trip();
+        }
+        tripped
+    };
+    // The raw micromath ROCOF noise alone trips a 1 Hz/s limit
+    #[cfg(not(any(feature = "libm", feature = "lut")))]
+    assert!(trips(0.0, 0.0));
+    assert!(!trips(0.0, 0.1));
+    assert!(!trips(0.5, 0.1));
+    assert!(trips(2.0, 0.1));
+}
+
+#[test]
+fn fast_reacquire_shortens_phase_step_recovery() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Samples after a phase step until theta stays within 0.02 rad of the input
+    let recovery = |step: f32, phase_jump_threshold: f32| {
+        let mut pll = SogiPll::new(PllConfig {
+            phase_jump_threshold,
+            fast_reacquire: true,
+            ..Default::default()
+        });
+        let mut last_outside = 0;
+        for n in 0..24000 {
+            let phase = if n < 12000 { 0.0 } else { step };
+            let result = pll.update(cosine(1.0, 50.0, phase, n));
+            let error = theta_error(result.theta - result.phase_step, 50.0, phase, n);
+            if n >= 12000 && error.abs() > 0.02 {
+                last_outside = n - 12000;
+            }
+        }
+        last_outside
+    };
+    for step in [0.5, 1.0, PI / 2.0] {
+        assert!(recovery(step, f32::INFINITY) > 500);
+        assert!(recovery(step, 0.03) < 250);
+        // Theta only snaps while the error is above the threshold, the PI loop slews the rest
+        assert!(recovery(step, 0.5) > 500);
+    }
+
+    // Noise alone does not look like a jump once locked
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut pll = SogiPll::new(PllConfig {
+        phase_jump_threshold: 0.03,
+        fast_reacquire: true,
+        ..Default::default()
+    });
+    for n in 0..24000 {
+        let result = pll.update(cosine(1.0, 50.0, 0.0, n) + rng.gen_range(-0.05..0.05));
+        assert!(n < 12000 || !result.phase_jump);
+    }
+}
+
+#[test]
+fn tustin_integrates_the_pi_output_with_the_trapezoid_rule() {
+    // 3 s of a 51Hz input at 1kHz, returning the final integrator
+    let run = |pi_discretization: PiDiscretization| {
+        let dt = 1.0 / 1000.0;
+        let mut pll = SogiPll::new(PllConfig {
+            sample_time: dt,
+            pi_integral_gain: 0.5,
+            pi_discretization,
+            ..Default::default()
+        });
+        let mut prev = pll.snapshot();
+        let mut result = pll.update(1.0);
+        for n in 1..3000 {
+            let state = pll.snapshot();
+            let step = state.pi_integral - prev.pi_integral;
+            let expected = match pi_discretization {
+                PiDiscretization::Euler => dt * prev.pi_value,
+                PiDiscretization::Tustin => dt / 2.0 * (prev.pi_value + state.pi_value),
+            };
+            // Within the rounding of an integrator near 19
+            assert!((step - expected).abs() < 1e-5);
+            prev = state;
+            result = pll.update((core::f64::consts::TAU * 51.0 * n as f64 / 1000.0).cos() as f32);
+        }
+        assert!((result.frequency_hz() - 51.0).abs() < 0.05);
+        pll.snapshot().pi_integral
+    };
+    let (euler, tustin) = (run(PiDiscretization::Euler), run(PiDiscretization::Tustin));
+    // Half a sample of the PI output apart, the 0.02% in the PiDiscretization docs
+    let difference = (tustin - euler) / euler;
+    assert!(difference > 1e-4 && difference < 3e-4);
+}
+
+#[test]
+fn initial_state_respects_the_integral_limit() {
+    // omega over the first two samples after a warm start at 52Hz, before the phase error of
+    // the seeded SOGI reaches the loop
+    let first_omegas = |pi_integral_limit: f32| {
+        let config = PllConfig {
+            pi_integral_gain: 1.0,
+            pi_integral_limit,
+            ..Default::default()
+        };
+        let mut pll = SogiPll::with_initial_state(config, 0.0, 2.0 * PI * 52.0);
+        assert!(pll.snapshot().pi_integral <= pi_integral_limit);
+        [0, 1].map(|n| pll.update(cosine(1.0, 52.0, 0.0, n)).omega)
+    };
+    // The 2 Hz offset needs an integral of 2π·2 with Ki = 1
+    for omega in first_omegas(20.0) {
+        assert!((omega - 2.0 * PI * 52.0).abs() < 0.01);
+    }
+    // Clamped to a 1 Hz reach it starts there instead of jumping on the first update
+    for omega in first_omegas(2.0 * PI) {
+        assert!((omega - 2.0 * PI * 51.0).abs() < 0.01);
+    }
+}
+
+#[test]
+fn peak_hold_holds_and_decays() {
+    // 1.5 for a second, then 0.75, with the default 1 s decay
+    let mut pll = SogiPll::new(PllConfig::default());
+    for n in 0..18000 {
+        let amplitude = if n < 12000 { 1.5 } else { 0.75 };
+        pll.update(cosine(amplitude, 50.0, 0.0, n));
+        if (6000..12000).contains(&n) {
+            // The micromath sqrt alone reads 1.5625
+            assert!((pll.peak_hold() - 1.5).abs() < 1e-3);
+        }
+    }
+    let decayed = 1.5 * (-0.5f32).exp();
+    assert!((pll.peak_hold() - decayed).abs() < 2e-3);
+}
+
+#[test]
+fn notch_removes_the_tone_from_the_phase_error() {
+    // Largest |phase_error| and the spread of the theta error over the second second of a
+    // 50Hz input with 10% of a 100Hz tone
+    let errors = |notch_frequency: f32| {
+        let mut pll = SogiPll::new(PllConfig {
+            notch_frequency,
+            ..Default::default()
+        });
+        let (mut phase_error, mut low, mut high) = (0.0f32, f32::MAX, f32::MIN);
+        for n in 0..24000 {
+            let v = cosine(1.0, 50.0, 0.0, n) + cosine(0.1, 100.0, 0.0, n);
+            let result = pll.update(v);
+            if n >= 12000 {
+                phase_error = phase_error.max(pll.phase_error().abs());
+                let error = theta_error(result.theta - result.phase_step, 50.0, 0.0, n);
+                low = low.min(error);
+                high = high.max(error);
+            }
+        }
+        (phase_error, high - low)
+    };
+    let (phase_error, spread) = errors(0.0);
+    let (notched_phase_error, notched_spread) = errors(100.0);
+    assert!(notched_phase_error < phase_error / 10.0);
+    // theta keeps the constant phase shift of the notch at 50Hz, but no longer ripples
+    assert!(notched_spread < spread / 10.0);
+}
+
+#[test]
+fn phase_error_is_within_one_percent_of_small_angles() {
+    // Mean phase_error over the second half second with the loop open, so theta runs at
+    // omega_zero a fixed angle behind the input
+    let mean_phase_error = |angle: f32| {
+        let mut pll = SogiPll::new(PllConfig {
+            pi_proportional_gain: 0.0,
+            pi_integral_gain: 0.0,
+            ..Default::default()
+        });
+        let mut sum = 0.0f64;
+        for n in 0..12000 {
+            // The micromath sqrt alone puts this 4% off at amplitude 1.5
+            pll.update(cosine(1.5, 50.0, angle, n));
+            if n >= 6000 {
+                sum += pll.phase_error() as f64;
+            }
+        }
+        (sum / 6000.0) as f32
+    };
+    for angle in [-0.2, -0.1, 0.05, 0.1, 0.2] {
+        assert!((mean_phase_error(angle) / angle - 1.0).abs() < 0.01);
+    }
+    // sin(0.3) is 1.5% below 0.3
+    assert!(mean_phase_error(0.3) / 0.3 - 1.0 < -0.01);
+}
+
+#[test]
+fn dq_normalized_is_per_unit_at_any_amplitude() {
+    let mut pll = SogiPll::new(PllConfig::default());
+    for n in 0..12000 {
+        // The micromath sqrt alone puts d at 0.96 for amplitude 1.5
+        let result = pll.update(cosine(1.5, 50.0, 0.0, n));
+        if n > 6000 {
+            let (d, q) = result.dq_normalized(result.v_alpha, result.v_beta);
+            assert!((d - 1.0).abs() < 3e-3);
+            // theta is already a phase step of 0.026 rad ahead of alpha/beta
+            assert!(q.abs() < 0.03);
+        }
+    }
+}
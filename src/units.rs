@@ -0,0 +1,41 @@
+//! Conversions between frequency units
+
+use crate::Float;
+
+/// Frequency in Hz to angular frequency in rad/s
+pub fn hz_to_omega<T: Float>(hz: T) -> T {
+    hz * T::PI2
+}
+
+/// Angular frequency in rad/s to frequency in Hz
+pub fn omega_to_hz<T: Float>(omega: T) -> T {
+    omega * T::FRAC_1_2PI
+}
+
+/// Electrical angular frequency in rad/s to mechanical speed in RPM of a machine with
+/// `pole_pairs` pole pairs
+pub fn omega_to_rpm<T: Float>(omega: T, pole_pairs: u32) -> T {
+    omega_to_hz(omega) * T::from_f32(60.0) / T::from_f32(pole_pairs as f32)
+}
+
+/// Unit for [`PllResult::angle`](crate::PllResult::angle)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AngleUnit {
+    /// 0..2π
+    Radians,
+    /// 0..360
+    Degrees,
+    /// 0..1
+    Turns,
+}
+
+/// Unit for [`PllResult::frequency`](crate::PllResult::frequency)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FreqUnit {
+    RadPerSec,
+    Hertz,
+}